@@ -0,0 +1,304 @@
+use bevy::prelude::*;
+
+use std::f32::consts::{PI, TAU};
+
+use crate::health::{Invulnerable, ProjectileImmunity};
+use crate::movement::planar_right;
+use crate::schedule::InGameSet;
+use crate::weapon::Energy;
+
+const ROLL_LEVELING_RATE: f32 = 4.0;
+
+/// Sideways movement, in the same units as forward movement speed.
+#[derive(Component, Debug)]
+pub struct Strafe {
+    pub speed: f32,
+}
+
+impl Strafe {
+    pub fn new(speed: f32) -> Self {
+        Self { speed }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Boost {
+    pub multiplier: f32,
+    pub energy_cost: f32,
+    pub duration: Timer,
+    pub cooldown: Timer,
+    pub active: bool,
+}
+
+impl Boost {
+    pub fn new(multiplier: f32, energy_cost: f32, seconds: f32, cooldown_seconds: f32) -> Self {
+        Self {
+            multiplier,
+            energy_cost,
+            duration: Timer::from_seconds(seconds, TimerMode::Once),
+            cooldown: ready_timer(cooldown_seconds),
+            active: false,
+        }
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        if self.active {
+            self.multiplier
+        } else {
+            1.0
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Dash {
+    pub distance: f32,
+    pub energy_cost: f32,
+    pub invulnerable_seconds: f32,
+    pub cooldown: Timer,
+}
+
+impl Dash {
+    pub fn new(
+        distance: f32,
+        energy_cost: f32,
+        invulnerable_seconds: f32,
+        cooldown_seconds: f32,
+    ) -> Self {
+        Self {
+            distance,
+            energy_cost,
+            invulnerable_seconds,
+            cooldown: ready_timer(cooldown_seconds),
+        }
+    }
+}
+
+/// Roll around the ship's nose. Rolling a full turn in one direction within `window` counts as a
+/// barrel roll and, off cooldown, briefly makes the ship immune to projectiles and kicks it
+/// sideways. With no roll input the ship levels itself out.
+#[derive(Component, Debug)]
+pub struct Roll {
+    pub speed: f32,
+    /// -1.0 (left) to 1.0 (right), set by whatever is flying the ship.
+    pub input: f32,
+    /// Current roll angle in `(-PI, PI]`.
+    pub angle: f32,
+    pub progress: f32,
+    pub window: Timer,
+    pub cooldown: Timer,
+    pub evasion_seconds: f32,
+    pub displacement: f32,
+}
+
+impl Roll {
+    pub fn new(
+        speed: f32,
+        window_seconds: f32,
+        cooldown_seconds: f32,
+        evasion_seconds: f32,
+        displacement: f32,
+    ) -> Self {
+        Self {
+            speed,
+            input: 0.0,
+            angle: 0.0,
+            progress: 0.0,
+            window: Timer::from_seconds(window_seconds, TimerMode::Once),
+            cooldown: ready_timer(cooldown_seconds),
+            evasion_seconds,
+            displacement,
+        }
+    }
+}
+
+#[derive(Event, Debug)]
+pub struct BarrelRoll {
+    pub entity: Entity,
+    /// -1.0 for a roll to the left, 1.0 for a roll to the right.
+    pub direction: f32,
+}
+
+impl BarrelRoll {
+    pub fn new(entity: Entity, direction: f32) -> Self {
+        Self { entity, direction }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ability {
+    Boost,
+    Dash { direction: Vec3 },
+}
+
+#[derive(Event, Debug)]
+pub struct UseAbilityEvent {
+    pub entity: Entity,
+    pub ability: Ability,
+}
+
+impl UseAbilityEvent {
+    pub fn new(entity: Entity, ability: Ability) -> Self {
+        Self { entity, ability }
+    }
+}
+
+pub struct AbilityPlugin;
+
+impl Plugin for AbilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            ((tick_boosts, tick_dashes), use_abilities, update_rolls)
+                .chain()
+                .in_set(InGameSet::EntityUpdates),
+        )
+        .add_event::<UseAbilityEvent>()
+        .add_event::<BarrelRoll>();
+    }
+}
+
+fn ready_timer(seconds: f32) -> Timer {
+    let mut timer = Timer::from_seconds(seconds, TimerMode::Once);
+    timer.tick(timer.duration());
+    timer
+}
+
+fn tick_boosts(mut query: Query<&mut Boost>, time: Res<Time>) {
+    for mut boost in query.iter_mut() {
+        boost.cooldown.tick(time.delta());
+        if boost.active && boost.duration.tick(time.delta()).finished() {
+            boost.active = false;
+        }
+    }
+}
+
+fn tick_dashes(mut query: Query<&mut Dash>, time: Res<Time>) {
+    for mut dash in query.iter_mut() {
+        dash.cooldown.tick(time.delta());
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn use_abilities(
+    mut commands: Commands,
+    mut use_ability_event_reader: EventReader<UseAbilityEvent>,
+    mut query: Query<(
+        &mut Transform,
+        Option<&mut Boost>,
+        Option<&mut Dash>,
+        Option<&mut Energy>,
+        Option<&mut Invulnerable>,
+    )>,
+) {
+    for &UseAbilityEvent { entity, ability } in use_ability_event_reader.read() {
+        let Ok((mut transform, boost, dash, mut energy, invulnerable)) = query.get_mut(entity)
+        else {
+            continue;
+        };
+        let mut try_consume_energy = |amount: f32| {
+            energy
+                .as_mut()
+                .is_none_or(|energy| energy.try_consume(amount))
+        };
+
+        match ability {
+            Ability::Boost => {
+                let Some(mut boost) = boost else {
+                    continue;
+                };
+                if boost.active || !boost.cooldown.finished() {
+                    continue;
+                }
+                if !try_consume_energy(boost.energy_cost) {
+                    continue;
+                }
+                boost.active = true;
+                boost.duration.reset();
+                boost.cooldown.reset();
+            }
+            Ability::Dash { direction } => {
+                let Some(mut dash) = dash else {
+                    continue;
+                };
+                if !dash.cooldown.finished() {
+                    continue;
+                }
+                if !try_consume_energy(dash.energy_cost) {
+                    continue;
+                }
+                dash.cooldown.reset();
+                transform.translation += direction.normalize_or_zero() * dash.distance;
+                match invulnerable {
+                    Some(mut invulnerable) => invulnerable.extend(dash.invulnerable_seconds),
+                    None => {
+                        commands
+                            .entity(entity)
+                            .insert(Invulnerable::new(dash.invulnerable_seconds));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn update_rolls(
+    mut commands: Commands,
+    mut barrel_roll_event_writer: EventWriter<BarrelRoll>,
+    mut query: Query<(Entity, &mut Transform, &mut Roll)>,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut roll) in query.iter_mut() {
+        roll.cooldown.tick(time.delta());
+
+        let delta = if roll.input == 0.0 {
+            roll.progress = 0.0;
+            -roll.angle * (1.0 - (-ROLL_LEVELING_RATE * time.delta_seconds()).exp())
+        } else {
+            roll.input.clamp(-1.0, 1.0) * roll.speed * time.delta_seconds()
+        };
+        transform.rotate_local_z(delta);
+        roll.angle = wrap_angle(roll.angle + delta);
+
+        if roll.input == 0.0 {
+            continue;
+        }
+
+        // start a new attempt when changing direction or running out of time
+        if roll.progress == 0.0 || roll.progress.signum() != delta.signum() {
+            roll.progress = 0.0;
+            roll.window.reset();
+        }
+        roll.progress += delta;
+        if roll.window.tick(time.delta()).finished() {
+            roll.progress = 0.0;
+            continue;
+        }
+        if roll.progress.abs() < TAU {
+            continue;
+        }
+
+        let direction = roll.progress.signum();
+        roll.progress = 0.0;
+        if !roll.cooldown.finished() {
+            continue;
+        }
+        roll.cooldown.reset();
+
+        let displacement = planar_right(&transform) * direction * roll.displacement;
+        transform.translation += displacement;
+        commands
+            .entity(entity)
+            .insert(ProjectileImmunity::new(roll.evasion_seconds));
+        barrel_roll_event_writer.send(BarrelRoll::new(entity, direction));
+    }
+}
+
+fn wrap_angle(angle: f32) -> f32 {
+    let wrapped = (angle + PI).rem_euclid(TAU) - PI;
+    if wrapped == -PI {
+        PI
+    } else {
+        wrapped
+    }
+}
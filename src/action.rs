@@ -0,0 +1,525 @@
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::mem;
+use std::path::PathBuf;
+
+use crate::player::{Player, MAX_LOCAL_PLAYERS};
+use crate::save_file::{self, Format};
+
+const DEFAULT_BINDINGS_PATH: &str = "assets/config/bindings.ron";
+const USER_BINDINGS_FILE: &str = "bindings.ron";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    ThrustForward,
+    ThrustReverse,
+    TurnLeft,
+    TurnRight,
+    RollLeft,
+    RollRight,
+    StrafeLeft,
+    StrafeRight,
+    Fire,
+    Boost,
+    Dash,
+    Pause,
+    /// Menus are moved through with their own actions rather than the flying ones, so a button
+    /// can do something different in each.
+    MenuUp,
+    MenuDown,
+    MenuSelect,
+}
+
+impl Action {
+    pub const ALL: [Action; 15] = [
+        Action::ThrustForward,
+        Action::ThrustReverse,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::RollLeft,
+        Action::RollRight,
+        Action::StrafeLeft,
+        Action::StrafeRight,
+        Action::Fire,
+        Action::Boost,
+        Action::Dash,
+        Action::Pause,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuSelect,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Matches the button on the player's gamepad, or on any connected gamepad if the player
+    /// has none assigned.
+    GamepadButton(GamepadButtonType),
+}
+
+impl Binding {
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Mouse(button) => format!("Mouse {:?}", button),
+            Binding::GamepadButton(button_type) => format!("Gamepad {:?}", button_type),
+        }
+    }
+}
+
+/// One player's bindings. An action can have any number of bindings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerBindings {
+    bindings: HashMap<Action, Vec<Binding>>,
+    /// Which connected gamepad this player uses, counting in connection order.
+    #[serde(default)]
+    pub gamepad: Option<usize>,
+}
+
+impl PlayerBindings {
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Which inputs trigger each action, for each local player.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct ActionMap {
+    players: Vec<PlayerBindings>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        use Action::*;
+        use Binding::*;
+
+        let first = [
+            (ThrustForward, vec![Key(KeyCode::KeyW)]),
+            (ThrustReverse, vec![Key(KeyCode::KeyS)]),
+            (TurnLeft, vec![Key(KeyCode::KeyA)]),
+            (TurnRight, vec![Key(KeyCode::KeyD)]),
+            (
+                RollLeft,
+                vec![
+                    Key(KeyCode::KeyK),
+                    GamepadButton(GamepadButtonType::LeftTrigger),
+                ],
+            ),
+            (
+                RollRight,
+                vec![
+                    Key(KeyCode::KeyL),
+                    GamepadButton(GamepadButtonType::RightTrigger),
+                ],
+            ),
+            (StrafeLeft, vec![Key(KeyCode::KeyQ)]),
+            (StrafeRight, vec![Key(KeyCode::KeyE)]),
+            (
+                Fire,
+                vec![
+                    Key(KeyCode::Space),
+                    Mouse(MouseButton::Left),
+                    GamepadButton(GamepadButtonType::RightTrigger2),
+                ],
+            ),
+            (
+                Boost,
+                vec![
+                    Key(KeyCode::ShiftLeft),
+                    GamepadButton(GamepadButtonType::South),
+                ],
+            ),
+            (
+                Dash,
+                vec![Key(KeyCode::KeyF), GamepadButton(GamepadButtonType::West)],
+            ),
+            (
+                Pause,
+                vec![
+                    Key(KeyCode::Escape),
+                    GamepadButton(GamepadButtonType::Start),
+                ],
+            ),
+            (
+                MenuUp,
+                vec![Key(KeyCode::KeyW), GamepadButton(GamepadButtonType::DPadUp)],
+            ),
+            (
+                MenuDown,
+                vec![
+                    Key(KeyCode::KeyS),
+                    GamepadButton(GamepadButtonType::DPadDown),
+                ],
+            ),
+            (
+                MenuSelect,
+                vec![Key(KeyCode::Space), GamepadButton(GamepadButtonType::South)],
+            ),
+        ];
+        let second = [
+            (ThrustForward, vec![Key(KeyCode::ArrowUp)]),
+            (ThrustReverse, vec![Key(KeyCode::ArrowDown)]),
+            (TurnLeft, vec![Key(KeyCode::ArrowLeft)]),
+            (TurnRight, vec![Key(KeyCode::ArrowRight)]),
+            (
+                RollLeft,
+                vec![
+                    Key(KeyCode::Comma),
+                    GamepadButton(GamepadButtonType::LeftTrigger),
+                ],
+            ),
+            (
+                RollRight,
+                vec![
+                    Key(KeyCode::Period),
+                    GamepadButton(GamepadButtonType::RightTrigger),
+                ],
+            ),
+            (StrafeLeft, vec![Key(KeyCode::KeyO)]),
+            (StrafeRight, vec![Key(KeyCode::KeyP)]),
+            (
+                Fire,
+                vec![
+                    Key(KeyCode::Enter),
+                    GamepadButton(GamepadButtonType::RightTrigger2),
+                ],
+            ),
+            (
+                Boost,
+                vec![
+                    Key(KeyCode::ShiftRight),
+                    GamepadButton(GamepadButtonType::South),
+                ],
+            ),
+            (
+                Dash,
+                vec![Key(KeyCode::Slash), GamepadButton(GamepadButtonType::West)],
+            ),
+            (Pause, vec![GamepadButton(GamepadButtonType::Start)]),
+            (
+                MenuUp,
+                vec![
+                    Key(KeyCode::ArrowUp),
+                    GamepadButton(GamepadButtonType::DPadUp),
+                ],
+            ),
+            (
+                MenuDown,
+                vec![
+                    Key(KeyCode::ArrowDown),
+                    GamepadButton(GamepadButtonType::DPadDown),
+                ],
+            ),
+            (
+                MenuSelect,
+                vec![Key(KeyCode::Enter), GamepadButton(GamepadButtonType::South)],
+            ),
+        ];
+        // players beyond the two sharing the keyboard play on a gamepad alone
+        let gamepad_only: HashMap<Action, Vec<Binding>> = first
+            .iter()
+            .map(|(action, bindings)| {
+                let buttons = bindings
+                    .iter()
+                    .copied()
+                    .filter(|binding| matches!(binding, GamepadButton(_)))
+                    .collect::<Vec<_>>();
+                (*action, buttons)
+            })
+            .filter(|(_, buttons)| !buttons.is_empty())
+            .collect();
+        let mut players = vec![
+            PlayerBindings {
+                bindings: first.into_iter().collect(),
+                gamepad: Some(0),
+            },
+            PlayerBindings {
+                bindings: second.into_iter().collect(),
+                gamepad: Some(1),
+            },
+        ];
+        players.extend(
+            (players.len()..MAX_LOCAL_PLAYERS as usize).map(|index| PlayerBindings {
+                bindings: gamepad_only.clone(),
+                gamepad: Some(index),
+            }),
+        );
+        Self { players }
+    }
+}
+
+impl ActionMap {
+    pub fn player(&self, player: Player) -> Option<&PlayerBindings> {
+        self.players.get(player.0 as usize)
+    }
+
+    /// Binds `action` for `player` to `binding` in place of any of its bindings on the same kind
+    /// of device, so rebinding a key leaves the mouse and gamepad bindings alone. Adds empty
+    /// bindings for any players in between if needed.
+    pub fn rebind(&mut self, player: Player, action: Action, binding: Binding) {
+        let index = player.0 as usize;
+        if self.players.len() <= index {
+            self.players.resize_with(index + 1, default);
+        }
+        let bindings = self.players[index].bindings.entry(action).or_default();
+        bindings.retain(|existing| mem::discriminant(existing) != mem::discriminant(&binding));
+        bindings.push(binding);
+    }
+
+    /// Loads the player's saved bindings, falling back to the shipped defaults file and then to
+    /// the built-in defaults.
+    pub fn load() -> Self {
+        if let Some(action_map) =
+            user_bindings_path().and_then(|path| save_file::load(&path, Self::parse))
+        {
+            return action_map;
+        }
+        if let Ok(contents) = fs::read_to_string(DEFAULT_BINDINGS_PATH) {
+            match Self::parse(&contents) {
+                Ok(action_map) => return action_map,
+                Err(error) => warn!(
+                    "ignoring invalid bindings in {:?}: {}",
+                    DEFAULT_BINDINGS_PATH, error
+                ),
+            }
+        }
+        Self::default()
+    }
+
+    /// Bindings saved before local multiplayer held the first player's bindings on their own,
+    /// and are taken as the first player's with the defaults for everyone else. Keys the second
+    /// player's defaults use are dropped from them, so one key press doesn't fly both ships.
+    fn parse(contents: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str::<Self>(contents)
+            .or_else(|error| {
+                let first = ron::from_str::<PlayerBindings>(contents).map_err(|_| error)?;
+                let mut action_map = Self::default();
+                let taken: HashSet<Binding> = action_map.players[1]
+                    .bindings
+                    .values()
+                    .flatten()
+                    .copied()
+                    .filter(|binding| matches!(binding, Binding::Key(_)))
+                    .collect();
+                let mut bindings = first.bindings;
+                for action_bindings in bindings.values_mut() {
+                    action_bindings.retain(|binding| !taken.contains(binding));
+                }
+                // anything left unbound is bound to its defaults again
+                bindings.retain(|_, action_bindings| !action_bindings.is_empty());
+                action_map.players[0].bindings = bindings;
+                Ok(action_map)
+            })
+            .map(Self::with_missing_defaults)
+    }
+
+    /// Binds any action added since the bindings were saved to its defaults, and gives any
+    /// player missing from them the default bindings.
+    fn with_missing_defaults(mut self) -> Self {
+        let mut defaults = Self::default().players;
+        for (player_bindings, defaults) in self.players.iter_mut().zip(defaults.iter()) {
+            for (action, bindings) in defaults.bindings.iter() {
+                player_bindings
+                    .bindings
+                    .entry(*action)
+                    .or_insert_with(|| bindings.clone());
+            }
+        }
+        if defaults.len() > self.players.len() {
+            self.players.extend(defaults.drain(self.players.len()..));
+        }
+        self
+    }
+
+    /// Saves the bindings as the player's own, to be picked up by `load` next time.
+    pub fn save(&self) {
+        let Some(path) = user_bindings_path() else {
+            return;
+        };
+        save_file::save(self, &path, Format::Ron);
+    }
+}
+
+fn user_bindings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(USER_BINDINGS_FILE))
+}
+
+/// The `index`th connected gamepad, counting in connection order.
+pub fn connected_gamepad(gamepads: &Gamepads, index: usize) -> Option<Gamepad> {
+    let mut connected: Vec<Gamepad> = gamepads.iter().collect();
+    connected.sort_by_key(|gamepad| gamepad.id);
+    connected.get(index).copied()
+}
+
+/// This frame's state of every player's actions, derived from the raw inputs through the
+/// `ActionMap`.
+#[derive(Resource, Debug, Default)]
+pub struct ActionState {
+    pressed: HashSet<(Player, Action)>,
+    just_pressed: HashSet<(Player, Action)>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, player: Player, action: Action) -> bool {
+        self.pressed.contains(&(player, action))
+    }
+
+    pub fn just_pressed(&self, player: Player, action: Action) -> bool {
+        self.just_pressed.contains(&(player, action))
+    }
+
+    /// Whether any player just pressed `action`, for actions that affect the whole game.
+    pub fn any_just_pressed(&self, action: Action) -> bool {
+        self.just_pressed
+            .iter()
+            .any(|&(_, just_pressed)| just_pressed == action)
+    }
+
+    /// -1.0, 0.0 or 1.0 depending on which of two opposing actions is held. Holding both
+    /// cancels out.
+    pub fn axis(&self, player: Player, negative: Action, positive: Action) -> f32 {
+        let mut value = 0.0;
+        if self.pressed(player, negative) {
+            value -= 1.0;
+        }
+        if self.pressed(player, positive) {
+            value += 1.0;
+        }
+        value
+    }
+}
+
+/// When set, the next key, mouse button or button on that player's gamepad pressed replaces
+/// their bindings of this action on the same kind of device. Pressing pause cancels it.
+#[derive(Resource, Debug, Default)]
+pub struct PendingRebind(pub Option<(Player, Action)>);
+
+pub struct ActionPlugin;
+
+impl Plugin for ActionPlugin {
+    fn build(&self, app: &mut App) {
+        // bindings inserted up front, e.g. by tests, are used instead of the saved ones
+        if !app.world.contains_resource::<ActionMap>() {
+            app.insert_resource(ActionMap::load());
+        }
+        app.init_resource::<ActionState>()
+            .init_resource::<PendingRebind>()
+            .add_systems(
+                PreUpdate,
+                (capture_rebind, update_action_state)
+                    .chain()
+                    .after(InputSystem),
+            );
+    }
+}
+
+fn capture_rebind(
+    mut pending_rebind: ResMut<PendingRebind>,
+    mut action_map: ResMut<ActionMap>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepad_button_input: Res<ButtonInput<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+) {
+    let Some((player, action)) = pending_rebind.0 else {
+        return;
+    };
+    let gamepad = action_map
+        .player(player)
+        .and_then(|player_bindings| player_bindings.gamepad)
+        .map(|index| connected_gamepad(&gamepads, index));
+    let binding = keyboard_input
+        .get_just_pressed()
+        .next()
+        .map(|&key| Binding::Key(key))
+        .or_else(|| {
+            mouse_input
+                .get_just_pressed()
+                .next()
+                .map(|&button| Binding::Mouse(button))
+        })
+        .or_else(|| {
+            gamepad_button_input
+                .get_just_pressed()
+                .find(|button| gamepad.is_none_or(|gamepad| gamepad == Some(button.gamepad)))
+                .map(|button| Binding::GamepadButton(button.button_type))
+        });
+    let Some(binding) = binding else {
+        return;
+    };
+
+    pending_rebind.0 = None;
+    // anyone's pause backs out without binding anything
+    let cancelled = action_map
+        .players
+        .iter()
+        .any(|player_bindings| player_bindings.bindings(Action::Pause).contains(&binding));
+    if cancelled {
+        return;
+    }
+    action_map.rebind(player, action, binding);
+    action_map.save();
+}
+
+fn update_action_state(
+    mut action_state: ResMut<ActionState>,
+    action_map: Res<ActionMap>,
+    pending_rebind: Res<PendingRebind>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepad_button_input: Res<ButtonInput<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+) {
+    action_state.pressed.clear();
+    action_state.just_pressed.clear();
+    // the key that was just captured for a rebind shouldn't also trigger its action
+    if pending_rebind.is_changed() {
+        return;
+    }
+
+    for (index, player_bindings) in action_map.players.iter().enumerate() {
+        let player = Player(index as u8);
+        let gamepad = player_bindings
+            .gamepad
+            .map(|index| connected_gamepad(&gamepads, index));
+        for (&action, bindings) in player_bindings.bindings.iter() {
+            for binding in bindings {
+                let (pressed, just_pressed) = match *binding {
+                    Binding::Key(key) => (
+                        keyboard_input.pressed(key),
+                        keyboard_input.just_pressed(key),
+                    ),
+                    Binding::Mouse(button) => (
+                        mouse_input.pressed(button),
+                        mouse_input.just_pressed(button),
+                    ),
+                    Binding::GamepadButton(button_type) => {
+                        let buttons = || {
+                            gamepads
+                                .iter()
+                                .filter(|&candidate| {
+                                    gamepad.is_none_or(|gamepad| gamepad == Some(candidate))
+                                })
+                                .map(move |gamepad| GamepadButton::new(gamepad, button_type))
+                        };
+                        (
+                            buttons().any(|button| gamepad_button_input.pressed(button)),
+                            buttons().any(|button| gamepad_button_input.just_pressed(button)),
+                        )
+                    }
+                };
+                if pressed {
+                    action_state.pressed.insert((player, action));
+                }
+                if just_pressed {
+                    action_state.just_pressed.insert((player, action));
+                }
+            }
+        }
+    }
+}
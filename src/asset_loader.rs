@@ -3,8 +3,8 @@ use bevy::prelude::*;
 #[derive(Resource, Debug, Default)]
 pub struct SceneAssets {
     pub asteroid: Handle<Scene>,
+    pub enemy: Handle<Scene>,
     pub missile: Handle<Scene>,
-    pub spaceship: Handle<Scene>,
 }
 
 pub struct AssetLoaderPlugin;
@@ -19,7 +19,7 @@ impl Plugin for AssetLoaderPlugin {
 fn load_assets(mut scene_assets: ResMut<SceneAssets>, asset_server: Res<AssetServer>) {
     *scene_assets = SceneAssets {
         asteroid: asset_server.load("Planet.glb#Scene0"),
+        enemy: asset_server.load("Spaceship.glb#Scene0"),
         missile: asset_server.load("Bullet.glb#Scene0"),
-        spaceship: asset_server.load("Spaceship.glb#Scene0"),
     }
 }
@@ -3,13 +3,20 @@ use rand::Rng;
 use std::ops::Range;
 
 use crate::asset_loader::SceneAssets;
+use crate::boss::no_active_boss;
 use crate::collision::{Collider, CollisionDamage};
-use crate::despawn::DespawnWhenRemote;
+use crate::despawn::{DespawnOnRunEnd, DespawnWhenRemote};
+use crate::faction::Faction;
 use crate::health::Health;
 use crate::movement::{Acceleration, MovingObjectBundle, Velocity};
+use crate::rng::GameRng;
 use crate::schedule::InGameSet;
+use crate::score::Points;
+use crate::state::OnRunStart;
 
-const RADIUS: f32 = 1.25;
+/// The radius the asteroid model is built at, which other sizes scale it from.
+const MODEL_RADIUS: f32 = 1.25;
+const RADIUS_RANGE: Range<f32> = 0.75..2.0;
 const VELOCITY_SCALAR: f32 = 5.0;
 const ACCELERATION_SCALAR: f32 = 1.0;
 const SPAWN_RANGE_X: Range<f32> = -25.0..25.0;
@@ -18,6 +25,8 @@ const SPAWN_TIME_SECONDS: f32 = 1.0;
 const ROTATE_SPEED: f32 = 2.5;
 const HEALTH: f32 = 80.0;
 const COLLISION_DAMAGE: f32 = 35.0;
+/// Bigger asteroids are worth more, from 30 points for the smallest to 80 for the biggest.
+const POINTS_PER_RADIUS: f32 = 40.0;
 
 #[derive(Resource, Debug)]
 pub struct SpawnTimer {
@@ -36,14 +45,21 @@ impl Plugin for AsteroidPlugin {
         })
         .add_systems(
             Update,
-            (spawn_asteroid, rotate_asteroids).in_set(InGameSet::EntityUpdates),
-        );
+            (spawn_asteroid.run_if(no_active_boss), rotate_asteroids)
+                .in_set(InGameSet::EntityUpdates),
+        )
+        .add_systems(OnRunStart, reset_spawn_timer);
     }
 }
 
+fn reset_spawn_timer(mut spawn_timer: ResMut<SpawnTimer>) {
+    spawn_timer.timer.reset();
+}
+
 fn spawn_asteroid(
     mut commands: Commands,
     mut spawn_timer: ResMut<SpawnTimer>,
+    mut rng: ResMut<GameRng>,
     time: Res<Time>,
     scene_assets: Res<SceneAssets>,
 ) {
@@ -51,7 +67,6 @@ fn spawn_asteroid(
     if !spawn_timer.timer.just_finished() {
         return;
     }
-    let mut rng = rand::thread_rng();
     let translation = Vec3::new(
         rng.gen_range(SPAWN_RANGE_X),
         0.,
@@ -62,22 +77,27 @@ fn spawn_asteroid(
         || Vec3::new(rng.gen_range(-1.0..1.0), 0., rng.gen_range(-1.0..1.0)).normalize_or_zero();
     let velocity = random_unit_vector() * VELOCITY_SCALAR;
     let acceleration = random_unit_vector() * ACCELERATION_SCALAR;
+    let radius = rng.gen_range(RADIUS_RANGE);
 
     commands.spawn((
         MovingObjectBundle {
             acceleration: Acceleration::new(acceleration),
-            collider: Collider::new(RADIUS),
+            collider: Collider::new(radius),
             velocity: Velocity::new(velocity),
             model: SceneBundle {
                 scene: scene_assets.asteroid.clone(),
-                transform: Transform::from_translation(translation),
+                transform: Transform::from_translation(translation)
+                    .with_scale(Vec3::splat(radius / MODEL_RADIUS)),
                 ..default()
             },
         },
         Asteroid,
+        Faction::Neutral,
         Health::new(HEALTH),
         CollisionDamage::new(COLLISION_DAMAGE),
         DespawnWhenRemote,
+        DespawnOnRunEnd,
+        Points::new((POINTS_PER_RADIUS * radius).round() as u32),
     ));
 }
 
@@ -0,0 +1,505 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use std::f32::consts::{PI, TAU};
+
+use crate::asset_loader::SceneAssets;
+use crate::collision::{Collider, CollisionDamage};
+use crate::config::ConfigPlugin;
+use crate::despawn::DespawnOnRunEnd;
+use crate::enemy::{spawn_enemy, EnemyKind, EnemyProfiles};
+use crate::faction::Faction;
+use crate::health::Health;
+use crate::movement::{Acceleration, Velocity};
+use crate::schedule::InGameSet;
+use crate::score::Points;
+use crate::spaceship::Spaceship;
+use crate::weapon::spawn_projectile;
+
+const BOSS_PROFILES_PATH: &str = "config/bosses.ron";
+/// Where bosses settle, across the far end of the arena from the players.
+const HOLD_Z: f32 = 30.0;
+const SWAY_X: f32 = 20.0;
+const SWAY_RATE: f32 = 0.3;
+/// How far outside its outermost hit zone a boss launches projectiles and minions.
+const LAUNCH_CLEARANCE: f32 = 3.0;
+const MINION_SPACING: f32 = 8.0;
+const WEAK_POINT_COLOR: Color = Color::rgb(1.0, 0.3, 0.1);
+const HEALTH_BAR_TEXT_SIZE: f32 = 20.0;
+const HEALTH_BAR_HEIGHT: f32 = 12.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum BossKind {
+    /// A heavily armoured battleship with an exposed reactor at the back.
+    Dreadnought,
+    /// Launches fighters from a hangar in its nose.
+    Carrier,
+}
+
+/// One volley of a boss's attack.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum AttackPattern {
+    /// A fan of `shots` aimed at the nearest player, `spread` radians apart.
+    Aimed { shots: u32, spread: f32 },
+    /// `shots` evenly spaced all the way round.
+    Ring { shots: u32 },
+    /// Like a ring, but each volley is turned `step` radians further than the last.
+    Spiral { shots: u32, step: f32 },
+    /// Launches `count` enemies of `kind` towards the players.
+    Launch { kind: EnemyKind, count: u32 },
+}
+
+/// A separately collidable part of a boss. Damage taken by any part is dealt to the boss as a
+/// whole, scaled by the part's multiplier.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HitZoneDefinition {
+    /// Relative to the boss, with +Z pointing towards the players.
+    pub offset: Vec3,
+    pub radius: f32,
+    /// Above 1.0 for weak points, which are shown glowing, and below it for armour.
+    pub damage_multiplier: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BossPhase {
+    /// The phase starts once health drops to this fraction of the maximum.
+    pub health_fraction: f32,
+    pub movement_speed: f32,
+    pub seconds_between_attacks: f32,
+    /// Used in turn, one per attack.
+    pub attacks: Vec<AttackPattern>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BossProfile {
+    pub name: String,
+    /// Of the model only; hit zones are in world units.
+    pub scale: f32,
+    pub health: f32,
+    pub collision_damage: f32,
+    /// Scored for destroying it.
+    #[serde(default)]
+    pub points: u32,
+    pub hit_zones: Vec<HitZoneDefinition>,
+    /// In order, the first starting at full health.
+    pub phases: Vec<BossPhase>,
+}
+
+impl BossProfile {
+    /// The distance from the centre to the outside of the furthest hit zone.
+    fn extent(&self) -> f32 {
+        self.hit_zones
+            .iter()
+            .map(|zone| zone.offset.length() + zone.radius)
+            .fold(0.0, f32::max)
+    }
+}
+
+/// How every kind of boss is built and fights, loaded from `BOSS_PROFILES_PATH`.
+#[derive(Asset, Resource, TypePath, Debug, Clone, Default, Deserialize)]
+pub struct BossProfiles {
+    profiles: HashMap<BossKind, BossProfile>,
+}
+
+impl BossProfiles {
+    pub fn get(&self, kind: BossKind) -> Option<&BossProfile> {
+        self.profiles.get(&kind)
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Boss {
+    pub kind: BossKind,
+    pub max_health: f32,
+    /// Index into the profile's phases.
+    pub phase: usize,
+    attack_timer: Timer,
+    next_attack: usize,
+    spiral_angle: f32,
+}
+
+impl Boss {
+    pub fn new(kind: BossKind, profile: &BossProfile) -> Self {
+        let seconds_between_attacks = profile
+            .phases
+            .first()
+            .map_or(1.0, |phase| phase.seconds_between_attacks);
+        Self {
+            kind,
+            max_health: profile.health,
+            phase: 0,
+            attack_timer: Timer::from_seconds(seconds_between_attacks, TimerMode::Repeating),
+            next_attack: 0,
+            spiral_angle: 0.0,
+        }
+    }
+}
+
+/// One collidable part of `boss`.
+#[derive(Component, Debug)]
+pub struct HitZone {
+    pub boss: Entity,
+    pub damage_multiplier: f32,
+}
+
+impl HitZone {
+    pub fn new(boss: Entity, damage_multiplier: f32) -> Self {
+        Self {
+            boss,
+            damage_multiplier,
+        }
+    }
+}
+
+#[derive(Event, Debug)]
+pub struct BossPhaseChanged {
+    pub entity: Entity,
+    /// Counting from 1, as shown to the player.
+    pub phase: usize,
+}
+
+impl BossPhaseChanged {
+    pub fn new(entity: Entity, phase: usize) -> Self {
+        Self { entity, phase }
+    }
+}
+
+/// The glowing sphere marking a weak point.
+#[derive(Resource, Debug)]
+struct WeakPointMarker {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+#[derive(Component, Debug)]
+struct BossHealthBar;
+
+#[derive(Component, Debug)]
+struct BossHealthBarFill;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ConfigPlugin::<BossProfiles>::new(BOSS_PROFILES_PATH))
+            .add_systems(Startup, create_weak_point_marker)
+            .add_systems(
+                Update,
+                (
+                    update_boss_phases,
+                    (boss_attacks, move_bosses),
+                    update_boss_health_bar,
+                )
+                    .chain()
+                    .in_set(InGameSet::EntityUpdates),
+            )
+            .add_event::<BossPhaseChanged>();
+    }
+}
+
+/// Run condition for anything that should hold off while a boss fight is on.
+pub fn no_active_boss(query: Query<(), With<Boss>>) -> bool {
+    query.is_empty()
+}
+
+#[derive(SystemParam)]
+pub struct BossSpawner<'w> {
+    scene_assets: Res<'w, SceneAssets>,
+    profiles: Res<'w, BossProfiles>,
+    weak_point_marker: Res<'w, WeakPointMarker>,
+}
+
+impl<'w> BossSpawner<'w> {
+    /// Spawns a boss facing down the arena towards the players, with a child entity for its
+    /// model and for each of its hit zones.
+    pub fn spawn(&self, commands: &mut Commands, kind: BossKind, translation: Vec3) {
+        let Some(profile) = self.profiles.get(kind) else {
+            warn!("no profile for {:?}", kind);
+            return;
+        };
+
+        let mut boss = commands.spawn((
+            SpatialBundle::from_transform(
+                Transform::from_translation(translation).with_rotation(Quat::from_rotation_y(PI)),
+            ),
+            Velocity::new(Vec3::ZERO),
+            Acceleration::new(Vec3::ZERO),
+            Boss::new(kind, profile),
+            Health::new(profile.health),
+            Faction::Enemy,
+            DespawnOnRunEnd,
+            Points::new(profile.points),
+        ));
+        let boss_entity = boss.id();
+        boss.with_children(|parent| {
+            parent.spawn(SceneBundle {
+                scene: self.scene_assets.enemy.clone(),
+                transform: Transform::from_scale(Vec3::splat(profile.scale)),
+                ..default()
+            });
+            for zone in profile.hit_zones.iter() {
+                let mut hit_zone = parent.spawn((
+                    SpatialBundle::from_transform(Transform::from_translation(zone.offset)),
+                    Collider::new(zone.radius),
+                    CollisionDamage::new(profile.collision_damage),
+                    HitZone::new(boss_entity, zone.damage_multiplier),
+                    Faction::Enemy,
+                ));
+                if zone.damage_multiplier > 1.0 {
+                    hit_zone.with_children(|parent| {
+                        parent.spawn(PbrBundle {
+                            mesh: self.weak_point_marker.mesh.clone(),
+                            material: self.weak_point_marker.material.clone(),
+                            transform: Transform::from_scale(Vec3::splat(zone.radius)),
+                            ..default()
+                        });
+                    });
+                }
+            }
+        });
+    }
+}
+
+fn create_weak_point_marker(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(WeakPointMarker {
+        mesh: meshes.add(Sphere::new(1.0)),
+        material: materials.add(StandardMaterial {
+            base_color: WEAK_POINT_COLOR,
+            emissive: WEAK_POINT_COLOR,
+            unlit: true,
+            ..default()
+        }),
+    });
+}
+
+/// Moves each boss on to the next phase once its health drops far enough. Phases never go
+/// back, and a big hit can skip one entirely.
+fn update_boss_phases(
+    mut boss_phase_changed_event_writer: EventWriter<BossPhaseChanged>,
+    mut query: Query<(Entity, &mut Boss, &Health)>,
+    profiles: Res<BossProfiles>,
+) {
+    for (entity, mut boss, health) in query.iter_mut() {
+        let Some(profile) = profiles.get(boss.kind) else {
+            continue;
+        };
+        let fraction = health.value / boss.max_health;
+        let Some(phase) = profile
+            .phases
+            .iter()
+            .rposition(|phase| fraction <= phase.health_fraction)
+        else {
+            continue;
+        };
+        if phase <= boss.phase {
+            continue;
+        }
+
+        boss.phase = phase;
+        boss.next_attack = 0;
+        boss.attack_timer = Timer::from_seconds(
+            profile.phases[phase].seconds_between_attacks,
+            TimerMode::Repeating,
+        );
+        boss_phase_changed_event_writer.send(BossPhaseChanged::new(entity, phase + 1));
+    }
+}
+
+fn boss_attacks(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Boss, &Transform, &Faction)>,
+    target_query: Query<&Transform, With<Spaceship>>,
+    profiles: Res<BossProfiles>,
+    enemy_profiles: Res<EnemyProfiles>,
+    scene_assets: Res<SceneAssets>,
+    time: Res<Time>,
+) {
+    for (entity, mut boss, transform, &faction) in query.iter_mut() {
+        if !boss.attack_timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let Some(profile) = profiles.get(boss.kind) else {
+            continue;
+        };
+        let Some(phase) = profile.phases.get(boss.phase) else {
+            continue;
+        };
+        if phase.attacks.is_empty() {
+            continue;
+        }
+        let attack = phase.attacks[boss.next_attack % phase.attacks.len()];
+        boss.next_attack += 1;
+
+        let clearance = profile.extent() + LAUNCH_CLEARANCE;
+        let nose = transform.rotation * Vec3::Z;
+        // angles around Y, where 0 is +Z
+        let headings: Vec<f32> = match attack {
+            AttackPattern::Aimed { shots, spread } => {
+                let Some(target) = target_query
+                    .iter()
+                    .map(|target| target.translation - transform.translation)
+                    .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+                else {
+                    continue;
+                };
+                let middle = (shots as f32 - 1.0) / 2.0;
+                (0..shots)
+                    .map(|shot| target.x.atan2(target.z) + (shot as f32 - middle) * spread)
+                    .collect()
+            }
+            AttackPattern::Ring { shots } => (0..shots)
+                .map(|shot| shot as f32 * TAU / shots as f32)
+                .collect(),
+            AttackPattern::Spiral { shots, step } => {
+                boss.spiral_angle = (boss.spiral_angle + step) % TAU;
+                (0..shots)
+                    .map(|shot| boss.spiral_angle + shot as f32 * TAU / shots as f32)
+                    .collect()
+            }
+            AttackPattern::Launch { kind, count } => {
+                let side = nose.cross(Vec3::Y);
+                let middle = (count as f32 - 1.0) / 2.0;
+                for minion in 0..count {
+                    let offset = side * (minion as f32 - middle) * MINION_SPACING;
+                    let translation = transform.translation + nose * clearance + offset;
+                    spawn_enemy(
+                        &mut commands,
+                        &scene_assets,
+                        &enemy_profiles,
+                        kind,
+                        translation,
+                    );
+                }
+                vec![]
+            }
+        };
+
+        for heading in headings {
+            let rotation = Quat::from_rotation_y(heading);
+            let translation = transform.translation + rotation * Vec3::Z * clearance;
+            spawn_projectile(
+                &mut commands,
+                &scene_assets,
+                entity,
+                None,
+                faction,
+                translation,
+                rotation,
+            );
+        }
+    }
+}
+
+/// Bosses come to a stop across the far end of the arena and sway from side to side, faster
+/// in later phases.
+fn move_bosses(
+    mut query: Query<(&Boss, &Transform, &mut Velocity)>,
+    profiles: Res<BossProfiles>,
+    time: Res<Time>,
+) {
+    let sway = (time.elapsed_seconds() * SWAY_RATE * TAU).sin() * SWAY_X;
+    for (boss, transform, mut velocity) in query.iter_mut() {
+        let Some(phase) = profiles
+            .get(boss.kind)
+            .and_then(|profile| profile.phases.get(boss.phase))
+        else {
+            continue;
+        };
+        let destination = Vec3::new(sway, 0.0, HOLD_Z);
+        velocity.value =
+            (destination - transform.translation).clamp_length_max(phase.movement_speed);
+    }
+}
+
+/// Shows the combined health of every boss on the field in a bar across the top of the screen.
+fn update_boss_health_bar(
+    mut commands: Commands,
+    boss_query: Query<(&Boss, &Health)>,
+    bar_query: Query<Entity, With<BossHealthBar>>,
+    mut fill_query: Query<&mut Style, With<BossHealthBarFill>>,
+    profiles: Res<BossProfiles>,
+) {
+    let Some((first, _)) = boss_query.iter().next() else {
+        for entity in bar_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+    if bar_query.is_empty() {
+        let name = profiles
+            .get(first.kind)
+            .map_or_else(String::new, |profile| profile.name.clone());
+        spawn_boss_health_bar(&mut commands, name);
+    }
+
+    let (health, max_health) =
+        boss_query
+            .iter()
+            .fold((0.0, 0.0), |(health, max_health), (boss, boss_health)| {
+                (
+                    health + boss_health.value.max(0.0),
+                    max_health + boss.max_health,
+                )
+            });
+    for mut style in fill_query.iter_mut() {
+        style.width = Val::Percent(100.0 * health / max_health);
+    }
+}
+
+fn spawn_boss_health_bar(commands: &mut Commands, name: String) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(16.0),
+                    left: Val::Percent(25.0),
+                    width: Val::Percent(50.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            BossHealthBar,
+            DespawnOnRunEnd,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                name,
+                TextStyle {
+                    font_size: HEALTH_BAR_TEXT_SIZE,
+                    ..default()
+                },
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Px(HEALTH_BAR_HEIGHT),
+                        ..default()
+                    },
+                    background_color: Color::rgba(1.0, 1.0, 1.0, 0.2).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: Color::ORANGE_RED.into(),
+                            ..default()
+                        },
+                        BossHealthBarFill,
+                    ));
+                });
+        });
+}
@@ -16,3 +16,14 @@ fn spawn_camera(mut commands: Commands) {
         ..default()
     });
 }
+
+/// Projects a window cursor position onto the y=0 plane the game is played on.
+pub fn cursor_to_gameplay_plane(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    cursor_position: Vec2,
+) -> Option<Vec3> {
+    let ray = camera.viewport_to_world(camera_transform, cursor_position)?;
+    let distance = ray.intersect_plane(Vec3::ZERO, Plane3d::new(Vec3::Y))?;
+    Some(ray.get_point(distance))
+}
@@ -2,9 +2,20 @@ use bevy::prelude::*;
 use bevy::utils::HashMap;
 
 use crate::asteroid::Asteroid;
-use crate::health::Health;
+use crate::boss::HitZone;
+use crate::enemy::Enemy;
+use crate::faction::{Faction, Hostility};
+use crate::health::{Health, Invulnerable, ProjectileImmunity};
+use crate::player::Player;
+use crate::powerup::Invincibility;
 use crate::schedule::InGameSet;
-use crate::spaceship::{Spaceship, SpaceshipMissile};
+use crate::shield::{Shield, ShieldDepleted};
+use crate::spaceship::Spaceship;
+use crate::spatial::SpatialGrid;
+use crate::state::OnRunEnd;
+use crate::weapon::Projectile;
+
+const BROADPHASE_CELL_SIZE: f32 = 10.0;
 
 #[derive(Component, Debug)]
 pub struct Collider {
@@ -21,6 +32,12 @@ impl Collider {
     }
 }
 
+/// Marks a collider that never moves, like a station or a wall. It's filed in the broadphase
+/// once rather than every frame, and only checked against colliders that do move. It's placed
+/// by its `Transform`, so it must not have a parent.
+#[derive(Component, Debug)]
+pub struct StaticCollider;
+
 #[derive(Component, Debug)]
 pub struct CollisionDamage {
     pub amount: f32,
@@ -47,59 +64,135 @@ impl CollisionEvent {
     }
 }
 
+/// Sent for every hit that gets through, whether the shield or the hull takes it.
+#[derive(Event, Debug)]
+pub struct DamageTaken {
+    pub entity: Entity,
+    /// The player credited with the hit: whoever fired the projectile, otherwise whoever
+    /// collided. `None` if no player dealt it.
+    pub player: Option<Player>,
+    /// The projectile that landed the hit, if it was one.
+    pub projectile: Option<Entity>,
+    pub amount: f32,
+}
+
+impl DamageTaken {
+    pub fn new(
+        entity: Entity,
+        player: Option<Player>,
+        projectile: Option<Entity>,
+        amount: f32,
+    ) -> Self {
+        Self {
+            entity,
+            player,
+            projectile,
+            amount,
+        }
+    }
+}
+
 pub struct CollisionPlugin;
 
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            collision_detection.in_set(InGameSet::CollisionDetection),
-        )
-        .add_systems(
-            Update,
-            (
+        app.insert_resource(SpatialGrid::new(BROADPHASE_CELL_SIZE))
+            .add_systems(
+                Update,
+                (
+                    (update_spatial_grid, update_static_colliders),
+                    collision_detection,
+                )
+                    .chain()
+                    .in_set(InGameSet::CollisionDetection),
+            )
+            .add_systems(
+                Update,
                 (
-                    handle_collisions::<Asteroid>,
-                    handle_collisions::<Spaceship>,
-                    handle_collisions::<SpaceshipMissile>,
-                ),
-                apply_collision_damage,
+                    (
+                        handle_collisions::<Asteroid>,
+                        handle_collisions::<Spaceship>,
+                        handle_collisions::<Enemy>,
+                        handle_collisions::<HitZone>,
+                        handle_collisions::<StaticCollider>,
+                        handle_collisions::<Projectile>,
+                    ),
+                    apply_collision_damage,
+                )
+                    .chain()
+                    .in_set(InGameSet::EntityUpdates),
             )
-                .chain()
-                .in_set(InGameSet::EntityUpdates),
-        )
-        .add_event::<CollisionEvent>();
+            .add_systems(OnRunEnd, reset_spatial_grid)
+            .add_event::<CollisionEvent>()
+            .add_event::<DamageTaken>();
     }
 }
 
-fn collision_detection(mut query: Query<(Entity, &GlobalTransform, &mut Collider)>) {
-    let mut colliding_entities: HashMap<Entity, Vec<Entity>> = HashMap::new();
+/// Static colliders are unfiled as they're removed, but only while the game is running, so
+/// the grid is started afresh rather than trusting it to have seen them go.
+fn reset_spatial_grid(mut grid: ResMut<SpatialGrid>) {
+    *grid = SpatialGrid::new(BROADPHASE_CELL_SIZE);
+}
 
-    // Detect collision
-    for (entity_a, transform_a, collider_a) in query.iter() {
-        for (entity_b, transform_b, collider_b) in query.iter() {
-            if entity_a != entity_b {
-                let distance = transform_a
-                    .translation()
-                    .distance(transform_b.translation());
+/// Refiles every moving collider under its current position, for the broadphase and for
+/// anything else that needs to find what's nearby.
+fn update_spatial_grid(
+    mut grid: ResMut<SpatialGrid>,
+    query: Query<(Entity, &GlobalTransform, &Collider), Without<StaticCollider>>,
+) {
+    grid.clear();
+    for (entity, transform, collider) in query.iter() {
+        grid.insert(entity, transform.translation(), collider.radius);
+    }
+}
 
-                if distance < collider_a.radius + collider_b.radius {
-                    colliding_entities
-                        .entry(entity_a)
-                        .or_insert_with(Vec::new)
-                        .push(entity_b);
-                }
+/// Files static colliders when they're placed, and unfiles them once they're gone. Static
+/// colliders never have a parent, so their `Transform` already places them in the world on the
+/// frame they spawn, before any `GlobalTransform` has been propagated.
+#[allow(clippy::type_complexity)]
+fn update_static_colliders(
+    mut grid: ResMut<SpatialGrid>,
+    mut removed: RemovedComponents<StaticCollider>,
+    query: Query<(Entity, &Transform, &Collider), (With<StaticCollider>, Changed<Transform>)>,
+) {
+    for entity in removed.read() {
+        grid.remove_static(entity);
+    }
+    for (entity, transform, collider) in query.iter() {
+        grid.insert_static(entity, transform.translation, collider.radius);
+    }
+}
+
+fn collision_detection(
+    mut query: Query<(Entity, &GlobalTransform, &mut Collider, Has<StaticCollider>)>,
+    grid: Res<SpatialGrid>,
+) {
+    // static colliders never look for collisions themselves, but hear about them from the
+    // moving colliders that find them
+    let mut static_collisions: HashMap<Entity, Vec<Entity>> = HashMap::new();
+    for (entity, transform, mut collider, is_static) in query.iter_mut() {
+        collider.colliding_entities.clear();
+        if is_static {
+            continue;
+        }
+        // the grid only returns colliders that actually overlap
+        for entry in grid.query(transform.translation(), collider.radius) {
+            if entry.entity == entity {
+                continue;
+            }
+            collider.colliding_entities.push(entry.entity);
+            if entry.is_static {
+                static_collisions
+                    .entry(entry.entity)
+                    .or_default()
+                    .push(entity);
             }
         }
     }
 
-    // Update colliders
-    for (entity, _transform, mut collider) in query.iter_mut() {
-        collider.colliding_entities.clear();
-        if let Some(collisions) = colliding_entities.get(&entity) {
-            collider
-                .colliding_entities
-                .extend(collisions.iter().copied());
+    for (entity, collisions) in static_collisions {
+        if let Ok((_, _, mut collider, _)) = query.get_mut(entity) {
+            collider.colliding_entities.extend(collisions);
         }
     }
 }
@@ -118,24 +211,151 @@ fn handle_collisions<T: Component>(
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn apply_collision_damage(
     mut collision_event_reader: EventReader<CollisionEvent>,
-    mut health_query: Query<&mut Health>,
-    collision_damage_query: Query<&CollisionDamage>,
+    mut shield_depleted_event_writer: EventWriter<ShieldDepleted>,
+    mut damage_taken_event_writer: EventWriter<DamageTaken>,
+    mut health_query: Query<(&mut Health, Option<&mut Shield>)>,
+    collision_damage_query: Query<(
+        &CollisionDamage,
+        Option<&Faction>,
+        Option<&Projectile>,
+        Option<&Player>,
+    )>,
+    target_query: Query<(
+        (Has<Invulnerable>, Has<Invincibility>),
+        Has<ProjectileImmunity>,
+        Option<&Faction>,
+        Option<&HitZone>,
+    )>,
+    hostility: Res<Hostility>,
 ) {
+    // a shot can touch several overlapping parts of a boss at once, but only lands once, on the
+    // part it hurts most
+    let mut hits: Vec<(Entity, Entity, f32)> = vec![];
+    let mut hit_indices: HashMap<(Entity, Entity), usize> = HashMap::new();
     for &CollisionEvent {
         entity,
         collided_entity,
     } in collision_event_reader.read()
     {
-        let Ok(mut health) = health_query.get_mut(entity) else {
+        let Ok(((invulnerable, invincible), projectile_immune, faction, hit_zone)) =
+            target_query.get(entity)
+        else {
+            continue;
+        };
+        if invulnerable || invincible {
+            continue;
+        }
+        // hits on any part of a boss damage the boss as a whole
+        let (target, damage_multiplier) = hit_zone.map_or((entity, 1.0), |hit_zone| {
+            (hit_zone.boss, hit_zone.damage_multiplier)
+        });
+
+        let Ok((_, attacker_faction, projectile, _)) = collision_damage_query.get(collided_entity)
+        else {
             continue;
         };
+        if let Some(projectile) = projectile {
+            // ships can't shoot themselves
+            if projectile.shooter == target || projectile_immune {
+                continue;
+            }
+        }
+        if !hostility.is_hostile(attacker_faction.copied(), faction.copied()) {
+            continue;
+        }
+
+        match hit_indices.get(&(target, collided_entity)) {
+            Some(&index) => hits[index].2 = hits[index].2.max(damage_multiplier),
+            None => {
+                hit_indices.insert((target, collided_entity), hits.len());
+                hits.push((target, collided_entity, damage_multiplier));
+            }
+        }
+    }
 
-        let Ok(collision_damage) = collision_damage_query.get(collided_entity) else {
+    for (target, collided_entity, damage_multiplier) in hits {
+        let Ok((collision_damage, _, projectile, attacker_player)) =
+            collision_damage_query.get(collided_entity)
+        else {
+            continue;
+        };
+        let Ok((mut health, shield)) = health_query.get_mut(target) else {
             continue;
         };
 
-        health.value -= collision_damage.amount;
+        let player = projectile.map_or(attacker_player.copied(), |projectile| projectile.player);
+        let mut damage = collision_damage.amount * damage_multiplier;
+        damage_taken_event_writer.send(DamageTaken::new(
+            target,
+            player,
+            projectile.map(|_| collided_entity),
+            damage,
+        ));
+        if let Some(mut shield) = shield {
+            let was_depleted = shield.is_depleted();
+            damage = shield.absorb(damage);
+            if !was_depleted && shield.is_depleted() {
+                shield_depleted_event_writer.send(ShieldDepleted::new(target));
+            }
+        }
+
+        health.value -= damage;
+        health.last_hit_by = player;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::player::Player;
+
+    const BOSS_HEALTH: f32 = 100.0;
+    const PROJECTILE_DAMAGE: f32 = 5.0;
+
+    #[test]
+    fn shot_into_overlapping_hit_zones_damages_boss_once() {
+        let mut app = App::new();
+        app.init_resource::<Hostility>()
+            .add_event::<CollisionEvent>()
+            .add_event::<DamageTaken>()
+            .add_event::<ShieldDepleted>()
+            .add_systems(Update, apply_collision_damage);
+
+        let boss = app
+            .world
+            .spawn((Health::new(BOSS_HEALTH), Faction::Enemy))
+            .id();
+        let core = app
+            .world
+            .spawn((HitZone::new(boss, 1.0), Faction::Enemy))
+            .id();
+        let weak_point = app
+            .world
+            .spawn((HitZone::new(boss, 2.0), Faction::Enemy))
+            .id();
+        let shooter = app.world.spawn_empty().id();
+        let projectile = app
+            .world
+            .spawn((
+                Projectile::new(shooter, Some(Player(0))),
+                CollisionDamage::new(PROJECTILE_DAMAGE),
+                Faction::Player,
+            ))
+            .id();
+
+        // as handle_collisions::<HitZone> reports it, once for each zone the shot overlaps
+        app.world.send_event(CollisionEvent::new(core, projectile));
+        app.world
+            .send_event(CollisionEvent::new(weak_point, projectile));
+        app.update();
+
+        let health = app.world.get::<Health>(boss).unwrap();
+        assert_eq!(health.value, BOSS_HEALTH - PROJECTILE_DAMAGE * 2.0);
+        assert_eq!(health.last_hit_by, Some(Player(0)));
+        assert_eq!(app.world.resource::<Events<DamageTaken>>().len(), 1);
     }
 }
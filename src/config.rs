@@ -0,0 +1,108 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use serde::de::DeserializeOwned;
+use std::marker::PhantomData;
+use thiserror::Error;
+
+/// Every config file loaded through a `ConfigPlugin`, kept loaded here and waited on by the
+/// loading screen.
+#[derive(Resource, Debug, Default)]
+pub struct ConfigFiles {
+    pub handles: Vec<UntypedHandle>,
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigLoaderError {
+    #[error("could not read config: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse config: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+/// Reads a RON file straight into an `A`, for config that needs nothing else loaded with it.
+struct ConfigLoader<A> {
+    marker: PhantomData<fn() -> A>,
+}
+
+impl<A> Default for ConfigLoader<A> {
+    fn default() -> Self {
+        Self {
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<A: Asset + DeserializeOwned> AssetLoader for ConfigLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = ConfigLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(ron::de::from_bytes(&bytes)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+/// Loads the config file at `path`, relative to the assets folder, into the `A` resource, and
+/// again whenever the file changes. Until it loads, or if it can't be, the resource holds
+/// `A::default()`.
+pub struct ConfigPlugin<A> {
+    path: &'static str,
+    marker: PhantomData<fn() -> A>,
+}
+
+impl<A> ConfigPlugin<A> {
+    pub fn new(path: &'static str) -> Self {
+        Self {
+            path,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<A: Asset + Resource + Clone + Default + DeserializeOwned> Plugin for ConfigPlugin<A> {
+    fn build(&self, app: &mut App) {
+        let path = self.path;
+        app.init_asset::<A>()
+            .register_asset_loader(ConfigLoader::<A>::default())
+            .init_resource::<A>()
+            .init_resource::<ConfigFiles>()
+            .add_systems(
+                PreStartup,
+                move |mut config_files: ResMut<ConfigFiles>, asset_server: Res<AssetServer>| {
+                    config_files
+                        .handles
+                        .push(asset_server.load::<A>(path).untyped());
+                },
+            )
+            .add_systems(PreUpdate, apply_config::<A>);
+    }
+}
+
+fn apply_config<A: Asset + Resource + Clone>(
+    mut asset_event_reader: EventReader<AssetEvent<A>>,
+    mut config: ResMut<A>,
+    assets: Res<Assets<A>>,
+) {
+    for event in asset_event_reader.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = *event {
+            if let Some(asset) = assets.get(id) {
+                *config = asset.clone();
+            }
+        }
+    }
+}
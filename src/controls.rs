@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+
+use crate::action::{connected_gamepad, Action, ActionMap, ActionState};
+use crate::player::Player;
+use crate::schedule::InGameSet;
+
+/// What a ship is being asked to do this frame. Control sources (keyboard, gamepad, AI, replay)
+/// fill this in during `ControlSourceSet`, and the ship systems act on it afterwards, so any
+/// ship can be flown by any source.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq)]
+pub struct ShipControls {
+    /// -1.0 (full reverse) to 1.0 (full forward).
+    pub thrust: f32,
+    /// -1.0 (full left) to 1.0 (full right).
+    pub turn: f32,
+    /// -1.0 (left) to 1.0 (right).
+    pub roll: f32,
+    /// -1.0 (left) to 1.0 (right).
+    pub strafe: f32,
+    pub fire: bool,
+    /// Triggers once, on the frame it is set.
+    pub boost: bool,
+    /// Triggers once, on the frame it is set.
+    pub dash: bool,
+}
+
+/// Marks a ship as flown through its `Player`'s bindings in the `ActionMap`: keyboard, mouse and
+/// gamepad buttons.
+#[derive(Component, Debug)]
+pub struct KeyboardControlled;
+
+/// Marks a ship as steered with the analog sticks of its `Player`'s gamepad, on top of any
+/// digital controls. A player without an assigned gamepad uses the first one connected.
+#[derive(Component, Debug)]
+pub struct GamepadControlled;
+
+/// Shapes raw stick input. Values inside `deadzone` read as zero and the rest are rescaled to
+/// 0..1 and raised to `response_exponent`, so values above 1.0 give finer control near the
+/// centre.
+#[derive(Resource, Debug)]
+pub struct AnalogSettings {
+    pub deadzone: f32,
+    pub response_exponent: f32,
+}
+
+impl Default for AnalogSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.15,
+            response_exponent: 2.0,
+        }
+    }
+}
+
+impl AnalogSettings {
+    pub fn shape(&self, value: f32) -> f32 {
+        let magnitude = value.abs();
+        if magnitude <= self.deadzone {
+            return 0.0;
+        }
+        let rescaled = ((magnitude - self.deadzone) / (1.0 - self.deadzone)).min(1.0);
+        rescaled.powf(self.response_exponent).copysign(value)
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemSet)]
+pub struct ControlSourceSet;
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AnalogSettings>()
+            .configure_sets(Update, ControlSourceSet.in_set(InGameSet::UserInput))
+            .add_systems(
+                Update,
+                (keyboard_ship_controls, gamepad_ship_controls)
+                    .chain()
+                    .in_set(ControlSourceSet),
+            );
+    }
+}
+
+fn keyboard_ship_controls(
+    mut query: Query<(&mut ShipControls, &Player), With<KeyboardControlled>>,
+    action_state: Res<ActionState>,
+) {
+    for (mut controls, &player) in query.iter_mut() {
+        *controls = ShipControls {
+            thrust: action_state.axis(player, Action::ThrustReverse, Action::ThrustForward),
+            turn: action_state.axis(player, Action::TurnLeft, Action::TurnRight),
+            roll: action_state.axis(player, Action::RollLeft, Action::RollRight),
+            strafe: action_state.axis(player, Action::StrafeLeft, Action::StrafeRight),
+            fire: action_state.pressed(player, Action::Fire),
+            boost: action_state.just_pressed(player, Action::Boost),
+            dash: action_state.just_pressed(player, Action::Dash),
+        };
+    }
+}
+
+/// Runs after the keyboard source and only overrides the axes the sticks are actually pushing.
+fn gamepad_ship_controls(
+    mut query: Query<(&mut ShipControls, &Player), With<GamepadControlled>>,
+    action_map: Res<ActionMap>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    analog_settings: Res<AnalogSettings>,
+) {
+    for (mut controls, &player) in query.iter_mut() {
+        let index = action_map
+            .player(player)
+            .and_then(|bindings| bindings.gamepad)
+            .unwrap_or(0);
+        let Some(gamepad) = connected_gamepad(&gamepads, index) else {
+            continue;
+        };
+        let axis = |axis_type| {
+            let value = axes
+                .get(GamepadAxis::new(gamepad, axis_type))
+                .unwrap_or(0.0);
+            analog_settings.shape(value)
+        };
+
+        let thrust = axis(GamepadAxisType::LeftStickY);
+        let turn = axis(GamepadAxisType::LeftStickX);
+        let strafe = axis(GamepadAxisType::RightStickX);
+        if thrust != 0.0 {
+            controls.thrust = thrust;
+        }
+        if turn != 0.0 {
+            controls.turn = turn;
+        }
+        if strafe != 0.0 {
+            controls.strafe = strafe;
+        }
+    }
+}
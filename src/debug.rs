@@ -4,14 +4,23 @@ use bevy::prelude::*;
 use iyes_perf_ui::diagnostics::{PerfUiEntryEntityCount, PerfUiEntryFPS, PerfUiEntryMemUsage};
 use iyes_perf_ui::{PerfUiAppExt, PerfUiEntry, PerfUiPlugin, PerfUiRoot};
 
+use crate::ability::BarrelRoll;
+use crate::boss::BossPhaseChanged;
 use crate::health::Health;
+use crate::player::Player;
+use crate::powerup::{PowerUpCollected, PowerUpExpired};
 use crate::schedule::InGameSet;
+use crate::score::ScoreChanged;
+use crate::shield::{Shield, ShieldDepleted, ShieldRestored};
 use crate::spaceship::Spaceship;
+use crate::wave::WaveStarted;
+use crate::weapon::{Energy, Heat, Weapon};
 
 pub struct DebugPlugin {
     pub enabled: bool,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for DebugPlugin {
     fn default() -> Self {
         Self { enabled: false }
@@ -28,15 +37,23 @@ impl Plugin for DebugPlugin {
                 .init_resource::<SpaceshipStatus>()
                 .add_perf_ui_entry_type::<PerfUiSpaceshipPosition>()
                 .add_perf_ui_entry_type::<PerfUiSpaceshipHealth>()
+                .add_perf_ui_entry_type::<PerfUiSpaceshipShield>()
+                .add_perf_ui_entry_type::<PerfUiSpaceshipWeapon>()
                 .add_systems(Startup, add_perf)
                 .add_systems(
                     Update,
-                    update_spaceship_status.after(InGameSet::EntityUpdates),
+                    (
+                        update_spaceship_status,
+                        log_gameplay_events,
+                        log_score_events,
+                    )
+                        .after(InGameSet::EntityUpdates),
                 );
         }
     }
 }
 
+#[allow(clippy::default_constructed_unit_structs)]
 fn add_perf(mut commands: Commands) {
     commands.spawn((
         PerfUiRoot { ..default() },
@@ -45,21 +62,36 @@ fn add_perf(mut commands: Commands) {
         PerfUiEntryEntityCount::default(),
         PerfUiSpaceshipPosition::default(),
         PerfUiSpaceshipHealth::default(),
+        PerfUiSpaceshipShield::default(),
+        PerfUiSpaceshipWeapon::default(),
     ));
 }
 
-// Custom PerfUiEntry for Spaceship status
+// Custom PerfUiEntry for Spaceship status, one value per ship in player order
 #[derive(Resource, Debug, Default)]
 struct SpaceshipStatus {
+    ships: Vec<ShipStatus>,
+}
+
+#[derive(Debug)]
+struct ShipStatus {
+    player: Option<Player>,
     translation: Vec3,
     health: f32,
+    shield: Option<f32>,
+    energy: f32,
+    heat: f32,
+    overheated: bool,
+    ammo: Option<u32>,
 }
 
+const SHIP_SEPARATOR: &str = " | ";
+
 #[derive(Component, Default)]
 struct PerfUiSpaceshipPosition;
 
 impl PerfUiEntry for PerfUiSpaceshipPosition {
-    type Value = (f32, f32);
+    type Value = Vec<(f32, f32)>;
     type SystemParam = SRes<SpaceshipStatus>;
 
     fn label(&self) -> &str {
@@ -74,13 +106,19 @@ impl PerfUiEntry for PerfUiSpaceshipPosition {
         &self,
         status: &mut <Self::SystemParam as SystemParam>::Item<'_, '_>,
     ) -> Option<Self::Value> {
-        let Vec3 { x, y: _, z: y } = status.translation;
-        Some((x, y))
+        let positions = status.ships.iter().map(|ship| {
+            let Vec3 { x, y: _, z: y } = ship.translation;
+            (x, y)
+        });
+        Some(positions.collect())
     }
 
-    fn format_value(&self, (x, y): &Self::Value) -> String {
-        let coords = format!("{:.2} x {:.2}", x, y);
-        coords
+    fn format_value(&self, value: &Self::Value) -> String {
+        let coords: Vec<String> = value
+            .iter()
+            .map(|(x, y)| format!("{:.2} x {:.2}", x, y))
+            .collect();
+        coords.join(SHIP_SEPARATOR)
     }
 
     fn width_hint(&self) -> usize {
@@ -92,7 +130,7 @@ impl PerfUiEntry for PerfUiSpaceshipPosition {
 struct PerfUiSpaceshipHealth;
 
 impl PerfUiEntry for PerfUiSpaceshipHealth {
-    type Value = f32;
+    type Value = Vec<f32>;
     type SystemParam = SRes<SpaceshipStatus>;
 
     fn label(&self) -> &str {
@@ -107,15 +145,17 @@ impl PerfUiEntry for PerfUiSpaceshipHealth {
         &self,
         status: &mut <Self::SystemParam as SystemParam>::Item<'_, '_>,
     ) -> Option<Self::Value> {
-        Some(status.health)
+        Some(status.ships.iter().map(|ship| ship.health).collect())
     }
 
     fn format_value(&self, value: &Self::Value) -> String {
-        value.to_string()
+        let health: Vec<String> = value.iter().map(f32::to_string).collect();
+        health.join(SHIP_SEPARATOR)
     }
 
+    #[allow(clippy::needless_return)]
     fn value_color(&self, value: &Self::Value) -> Option<Color> {
-        if *value <= 0.0 {
+        if value.iter().any(|&health| health <= 0.0) {
             return Some(Color::ORANGE_RED);
         } else {
             return None;
@@ -127,12 +167,174 @@ impl PerfUiEntry for PerfUiSpaceshipHealth {
     }
 }
 
+#[derive(Component, Default)]
+struct PerfUiSpaceshipShield;
+
+impl PerfUiEntry for PerfUiSpaceshipShield {
+    type Value = Vec<f32>;
+    type SystemParam = SRes<SpaceshipStatus>;
+
+    fn label(&self) -> &str {
+        "Ship shield"
+    }
+
+    fn sort_key(&self) -> i32 {
+        6
+    }
+
+    fn update_value(
+        &self,
+        status: &mut <Self::SystemParam as SystemParam>::Item<'_, '_>,
+    ) -> Option<Self::Value> {
+        let shields: Vec<f32> = status.ships.iter().filter_map(|ship| ship.shield).collect();
+        (!shields.is_empty()).then_some(shields)
+    }
+
+    fn format_value(&self, value: &Self::Value) -> String {
+        let shields: Vec<String> = value
+            .iter()
+            .map(|shield| format!("{:.0}", shield))
+            .collect();
+        shields.join(SHIP_SEPARATOR)
+    }
+
+    fn value_color(&self, value: &Self::Value) -> Option<Color> {
+        if value.iter().any(|&shield| shield <= 0.0) {
+            Some(Color::ORANGE_RED)
+        } else {
+            None
+        }
+    }
+
+    fn width_hint(&self) -> usize {
+        16
+    }
+}
+
+#[derive(Component, Default)]
+struct PerfUiSpaceshipWeapon;
+
+impl PerfUiEntry for PerfUiSpaceshipWeapon {
+    type Value = Vec<(f32, f32, bool, Option<u32>)>;
+    type SystemParam = SRes<SpaceshipStatus>;
+
+    fn label(&self) -> &str {
+        "Ship energy/heat/ammo"
+    }
+
+    fn sort_key(&self) -> i32 {
+        7
+    }
+
+    fn update_value(
+        &self,
+        status: &mut <Self::SystemParam as SystemParam>::Item<'_, '_>,
+    ) -> Option<Self::Value> {
+        let weapons = status
+            .ships
+            .iter()
+            .map(|ship| (ship.energy, ship.heat, ship.overheated, ship.ammo));
+        Some(weapons.collect())
+    }
+
+    fn format_value(&self, value: &Self::Value) -> String {
+        let weapons: Vec<String> = value
+            .iter()
+            .map(|(energy, heat, _overheated, ammo)| {
+                let ammo = match ammo {
+                    Some(ammo) => ammo.to_string(),
+                    None => "inf".to_string(),
+                };
+                format!("{:.0} / {:.0}% / {}", energy, heat * 100.0, ammo)
+            })
+            .collect();
+        weapons.join(SHIP_SEPARATOR)
+    }
+
+    fn value_color(&self, value: &Self::Value) -> Option<Color> {
+        if value
+            .iter()
+            .any(|&(_energy, _heat, overheated, _ammo)| overheated)
+        {
+            Some(Color::ORANGE_RED)
+        } else {
+            None
+        }
+    }
+
+    fn width_hint(&self) -> usize {
+        20
+    }
+}
+
+#[allow(clippy::type_complexity)]
 fn update_spaceship_status(
-    query: Query<(&Transform, &Health), With<Spaceship>>,
+    query: Query<
+        (
+            (&Transform, &Health, Option<&Shield>, Option<&Player>),
+            (Option<&Energy>, Option<&Heat>, Option<&Weapon>),
+        ),
+        With<Spaceship>,
+    >,
     mut status: ResMut<SpaceshipStatus>,
 ) {
-    if let Ok((transform, health)) = query.get_single() {
-        status.translation = transform.translation;
-        status.health = health.value;
+    status.ships = query
+        .iter()
+        .map(
+            |((transform, health, shield, player), (energy, heat, weapon))| ShipStatus {
+                player: player.copied(),
+                translation: transform.translation,
+                health: health.value,
+                shield: shield.map(|shield| shield.current),
+                energy: energy.map_or(0.0, |energy| energy.value),
+                heat: heat.map_or(0.0, |heat| heat.fraction()),
+                overheated: heat.is_some_and(|heat| heat.overheated),
+                ammo: weapon.and_then(|weapon| weapon.ammo),
+            },
+        )
+        .collect();
+    status.ships.sort_by_key(|ship| ship.player);
+}
+
+fn log_gameplay_events(
+    mut shield_depleted_event_reader: EventReader<ShieldDepleted>,
+    mut shield_restored_event_reader: EventReader<ShieldRestored>,
+    mut barrel_roll_event_reader: EventReader<BarrelRoll>,
+    mut power_up_collected_event_reader: EventReader<PowerUpCollected>,
+    mut power_up_expired_event_reader: EventReader<PowerUpExpired>,
+    mut wave_started_event_reader: EventReader<WaveStarted>,
+    mut boss_phase_changed_event_reader: EventReader<BossPhaseChanged>,
+) {
+    for ShieldDepleted { entity } in shield_depleted_event_reader.read() {
+        info!("shield depleted: {:?}", entity);
+    }
+    for ShieldRestored { entity } in shield_restored_event_reader.read() {
+        info!("shield restored: {:?}", entity);
+    }
+    for BarrelRoll { entity, direction } in barrel_roll_event_reader.read() {
+        info!("barrel roll: {:?} ({})", entity, direction);
+    }
+    for PowerUpCollected { entity, kind } in power_up_collected_event_reader.read() {
+        info!("power-up collected: {:?} ({:?})", entity, kind);
+    }
+    for PowerUpExpired { entity, kind } in power_up_expired_event_reader.read() {
+        info!("power-up expired: {:?} ({:?})", entity, kind);
+    }
+    for WaveStarted { number } in wave_started_event_reader.read() {
+        info!("wave {} started", number);
+    }
+    for BossPhaseChanged { entity, phase } in boss_phase_changed_event_reader.read() {
+        info!("boss {:?} entered phase {}", entity, phase);
+    }
+}
+
+fn log_score_events(mut score_changed_event_reader: EventReader<ScoreChanged>) {
+    for ScoreChanged {
+        player,
+        points,
+        score,
+    } in score_changed_event_reader.read()
+    {
+        info!("{:?} scored {} ({} total)", player, points, score);
     }
 }
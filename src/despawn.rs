@@ -1,12 +1,17 @@
 use bevy::prelude::*;
 
-use crate::{health::Health, schedule::InGameSet};
+use crate::{health::Health, schedule::InGameSet, state::OnRunEnd};
 
 const DESPAWN_DISTANCE: f32 = 100.;
 
 #[derive(Component, Debug)]
 pub struct DespawnWhenRemote;
 
+/// Anything belonging to the current run, cleared away when the run is left so the next one
+/// starts from an empty arena.
+#[derive(Component, Debug)]
+pub struct DespawnOnRunEnd;
+
 pub struct DespawnPlugin;
 
 impl Plugin for DespawnPlugin {
@@ -14,7 +19,8 @@ impl Plugin for DespawnPlugin {
         app.add_systems(
             Update,
             (despawn_remote_entities, despawn_dead_entities).in_set(InGameSet::DespawnEntities),
-        );
+        )
+        .add_systems(OnRunEnd, despawn_run_entities);
     }
 }
 
@@ -37,3 +43,9 @@ fn despawn_dead_entities(mut commands: Commands, query: Query<(Entity, &Health)>
         }
     }
 }
+
+fn despawn_run_entities(mut commands: Commands, query: Query<Entity, With<DespawnOnRunEnd>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use std::f32::consts::PI;
+
+use crate::ability::Strafe;
+use crate::asset_loader::SceneAssets;
+use crate::collision::{Collider, CollisionDamage};
+use crate::config::ConfigPlugin;
+use crate::controls::{ControlSourceSet, ShipControls};
+use crate::despawn::DespawnOnRunEnd;
+use crate::faction::Faction;
+use crate::health::Health;
+use crate::movement::{planar_right, Acceleration, MovingObjectBundle, Velocity};
+use crate::score::Points;
+use crate::ship_definition::WeaponDefinition;
+use crate::spaceship::{angle_to, turn_towards, ShipStats, Spaceship};
+use crate::steering::{Flock, Steering, SteeringBehaviour};
+
+const ENEMY_PROFILES_PATH: &str = "config/enemies.ron";
+/// How far ahead enemies look when turning their steering into controls.
+const STEERING_RESPONSE_SECONDS: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum EnemyKind {
+    /// Flies straight at the player to ram them.
+    Kamikaze,
+    /// Hangs back and takes careful shots from long range.
+    Sniper,
+    /// Circles at mid range, sliding side to side while firing.
+    Strafer,
+    /// Small and fragile, but arrives in flocking swarms.
+    Drone,
+}
+
+/// How an enemy ship is built and how it flies.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnemyProfile {
+    pub collider_radius: f32,
+    pub movement_speed: f32,
+    pub rotation_speed: f32,
+    pub health: f32,
+    pub collision_damage: f32,
+    /// Scored for destroying one.
+    #[serde(default)]
+    pub points: u32,
+    /// `None` for enemies that don't shoot.
+    pub weapon: Option<WeaponDefinition>,
+    /// How hard the steering behaviours can push.
+    pub acceleration: f32,
+    /// Weighted steering behaviours, aimed at the nearest player.
+    pub steering: Vec<(SteeringBehaviour, f32)>,
+    /// Only fires at targets this close.
+    pub fire_range: f32,
+    /// Only fires with the target within this angle either side of the nose, in radians.
+    pub fire_arc: f32,
+    /// Sideways speed; zero for enemies that don't strafe.
+    #[serde(default)]
+    pub strafe_speed: f32,
+    /// Shrinks or grows the model, which is full size if not given.
+    #[serde(default)]
+    pub model_scale: Option<f32>,
+    /// How many arrive together, in a cluster, each time one is due to spawn.
+    #[serde(default)]
+    pub group_size: u32,
+}
+
+/// The behaviour profile of every kind of enemy, loaded from `ENEMY_PROFILES_PATH`.
+#[derive(Asset, Resource, TypePath, Debug, Clone, Default, Deserialize)]
+pub struct EnemyProfiles {
+    profiles: HashMap<EnemyKind, EnemyProfile>,
+}
+
+impl EnemyProfiles {
+    pub fn get(&self, kind: EnemyKind) -> Option<&EnemyProfile> {
+        self.profiles.get(&kind)
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Enemy {
+    pub kind: EnemyKind,
+}
+
+impl Enemy {
+    pub fn new(kind: EnemyKind) -> Self {
+        Self { kind }
+    }
+}
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ConfigPlugin::<EnemyProfiles>::new(ENEMY_PROFILES_PATH))
+            .add_systems(Update, enemy_ship_controls.in_set(ControlSourceSet));
+    }
+}
+
+/// Spawns an enemy facing down the arena towards the players. Enemies are flown through
+/// `ShipControls` like any other ship, so they share the player's movement and weapon systems.
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    scene_assets: &SceneAssets,
+    profiles: &EnemyProfiles,
+    kind: EnemyKind,
+    translation: Vec3,
+) {
+    let Some(profile) = profiles.get(kind) else {
+        warn!("no profile for {:?}", kind);
+        return;
+    };
+
+    let mut enemy = commands.spawn((
+        MovingObjectBundle {
+            acceleration: Acceleration::new(Vec3::ZERO),
+            collider: Collider::new(profile.collider_radius),
+            velocity: Velocity::new(Vec3::ZERO),
+            model: SceneBundle {
+                scene: scene_assets.enemy.clone(),
+                transform: Transform::from_translation(translation)
+                    .with_rotation(Quat::from_rotation_y(PI))
+                    .with_scale(Vec3::splat(profile.model_scale.unwrap_or(1.0))),
+                ..default()
+            },
+        },
+        Enemy::new(kind),
+        Faction::Enemy,
+        DespawnOnRunEnd,
+        Points::new(profile.points),
+        ShipStats::new(profile.movement_speed, profile.rotation_speed),
+        ShipControls::default(),
+        Health::new(profile.health),
+        CollisionDamage::new(profile.collision_damage),
+        Steering::new(profile.movement_speed, profile.acceleration)
+            .with_behaviours(&profile.steering),
+    ));
+    if let Some(weapon) = &profile.weapon {
+        enemy.insert(weapon.weapon());
+    }
+    if profile.strafe_speed > 0.0 {
+        enemy.insert(Strafe::new(profile.strafe_speed));
+    }
+    if profile
+        .steering
+        .iter()
+        .any(|(behaviour, _)| behaviour.flocking_radius().is_some())
+    {
+        enemy.insert(Flock);
+    }
+}
+
+/// Points each enemy's steering at the nearest player ship and turns the result into controls,
+/// firing when the target is lined up and in range.
+#[allow(clippy::type_complexity)]
+fn enemy_ship_controls(
+    mut query: Query<(
+        (&mut ShipControls, &mut Steering, &mut Acceleration),
+        (&Enemy, &Transform, &Velocity, &ShipStats, Option<&Strafe>),
+    )>,
+    target_query: Query<(Entity, &Transform), With<Spaceship>>,
+    profiles: Res<EnemyProfiles>,
+    time: Res<Time>,
+) {
+    for (
+        (mut controls, mut steering, mut acceleration),
+        (enemy, transform, velocity, stats, strafe),
+    ) in query.iter_mut()
+    {
+        *controls = ShipControls::default();
+        let Some(profile) = profiles.get(enemy.kind) else {
+            continue;
+        };
+        let Some((target_entity, target)) = target_query
+            .iter()
+            .map(|(entity, target)| (entity, target.translation))
+            .min_by(|(_, a), (_, b)| {
+                let a = a.distance_squared(transform.translation);
+                let b = b.distance_squared(transform.translation);
+                a.total_cmp(&b)
+            })
+        else {
+            steering.target = None;
+            continue;
+        };
+        steering.target = Some(target_entity);
+
+        // ships fly by their controls, so the steering becomes stick input instead of
+        // accelerating the ship directly
+        let desired_velocity = (velocity.value + acceleration.value * STEERING_RESPONSE_SECONDS)
+            .clamp_length_max(stats.movement_speed);
+        acceleration.value = Vec3::ZERO;
+        let nose = -*transform.forward();
+        controls.thrust = (desired_velocity.dot(nose) / stats.movement_speed).clamp(-1.0, 1.0);
+        if let Some(strafe) = strafe {
+            controls.strafe =
+                (desired_velocity.dot(planar_right(transform)) / strafe.speed).clamp(-1.0, 1.0);
+        }
+
+        // gunships keep their nose on the target, the rest look where they're going
+        let facing = if profile.weapon.is_some() {
+            target
+        } else {
+            transform.translation + desired_velocity
+        };
+        if let Some(angle) = angle_to(transform, facing) {
+            controls.turn = turn_towards(angle, stats.rotation_speed, time.delta_seconds());
+        }
+
+        let distance = transform.translation.distance(target);
+        controls.fire = profile.weapon.is_some()
+            && distance <= profile.fire_range
+            && angle_to(transform, target).is_some_and(|angle| angle.abs() <= profile.fire_arc);
+    }
+}
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+
+use crate::player::MatchMode;
+use crate::settings::Settings;
+
+/// Which side an entity is on. Projectiles take the faction of whoever fired them.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Faction {
+    /// Hazards like asteroids, which get in everyone's way.
+    #[default]
+    Neutral,
+    Player,
+    Enemy,
+}
+
+/// Which factions can damage which. Entities without a `Faction` can damage and be damaged by
+/// anything.
+#[derive(Resource, Debug)]
+pub struct Hostility {
+    /// `(attacker, victim)` pairs where the attacker deals damage.
+    hostile: HashSet<(Faction, Faction)>,
+}
+
+impl Default for Hostility {
+    fn default() -> Self {
+        use Faction::*;
+
+        Self {
+            hostile: [
+                (Neutral, Player),
+                (Neutral, Enemy),
+                (Player, Neutral),
+                (Player, Enemy),
+                (Enemy, Neutral),
+                (Enemy, Player),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+}
+
+impl Hostility {
+    pub fn is_hostile(&self, attacker: Option<Faction>, victim: Option<Faction>) -> bool {
+        match (attacker, victim) {
+            (Some(attacker), Some(victim)) => self.hostile.contains(&(attacker, victim)),
+            _ => true,
+        }
+    }
+
+    pub fn set_hostile(&mut self, attacker: Faction, victim: Faction, hostile: bool) {
+        if hostile {
+            self.hostile.insert((attacker, victim));
+        } else {
+            self.hostile.remove(&(attacker, victim));
+        }
+    }
+}
+
+pub struct FactionPlugin;
+
+impl Plugin for FactionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hostility>().add_systems(
+            Update,
+            apply_match_mode.run_if(resource_changed::<Settings>),
+        );
+    }
+}
+
+/// Players only fight each other in versus.
+fn apply_match_mode(mut hostility: ResMut<Hostility>, settings: Res<Settings>) {
+    let versus = settings.match_mode == MatchMode::Versus;
+    hostility.set_hostile(Faction::Player, Faction::Player, versus);
+}
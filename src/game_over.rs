@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::asteroid::Asteroid;
+use crate::collision::DamageTaken;
+use crate::health::Health;
+use crate::player::Player;
+use crate::save_file::{self, Format};
+use crate::schedule::InGameSet;
+use crate::score::Score;
+use crate::spaceship::RunOver;
+use crate::state::{GameState, OnRunEnd, OnRunStart, RunTime};
+use crate::wave::WaveState;
+use crate::weapon::Projectile;
+
+const RUN_SUMMARY_FILE: &str = "last_run.json";
+const SLOW_MOTION_SPEED: f32 = 0.2;
+/// How long the slow-motion beat lasts in real time, before everything stops.
+const SLOW_MOTION_SECONDS: f32 = 1.5;
+
+/// Running totals for the current run, wrapped up in its `RunSummary`. Only what the players
+/// do and have done to them counts.
+#[derive(Resource, Debug, Default)]
+pub struct RunStats {
+    pub asteroids_destroyed: u32,
+    pub shots_fired: u32,
+    pub shots_hit: u32,
+    pub damage_taken: f32,
+}
+
+/// How the last run went, for the game over screen and saved as JSON.
+#[derive(Resource, Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub score: u32,
+    /// Counting from 1, as shown to the player.
+    pub wave: u32,
+    pub seconds_survived: f32,
+    pub asteroids_destroyed: u32,
+    pub shots_fired: u32,
+    /// The fraction of shots fired that hit something.
+    pub accuracy: f32,
+    pub damage_taken: f32,
+}
+
+impl RunSummary {
+    pub fn save(&self, path: &Path) {
+        save_file::save(self, path, Format::Json);
+    }
+}
+
+/// Where each run's summary is written as it ends, or `None` to keep it in memory only.
+#[derive(Resource, Debug)]
+pub struct RunSummaryPath(pub Option<PathBuf>);
+
+impl Default for RunSummaryPath {
+    fn default() -> Self {
+        Self(dirs::data_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(RUN_SUMMARY_FILE)))
+    }
+}
+
+/// Counts down the real time left in the slow-motion beat at the end of a run.
+#[derive(Resource, Debug)]
+struct SlowMotion {
+    timer: Timer,
+}
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .init_resource::<RunSummaryPath>()
+            .add_systems(OnRunStart, (reset_run_stats, restore_time))
+            .add_systems(OnRunEnd, restore_time)
+            .add_systems(Update, track_run_stats.in_set(InGameSet::DespawnEntities))
+            .add_systems(
+                Update,
+                (start_slow_motion, finish_slow_motion)
+                    .chain()
+                    .in_set(InGameSet::EntityUpdates),
+            )
+            .add_systems(OnEnter(GameState::GameOver), summarize_run);
+    }
+}
+
+fn reset_run_stats(mut run_stats: ResMut<RunStats>) {
+    *run_stats = RunStats::default();
+}
+
+fn restore_time(mut commands: Commands, mut time: ResMut<Time<Virtual>>) {
+    commands.remove_resource::<SlowMotion>();
+    time.set_relative_speed(1.0);
+    time.unpause();
+}
+
+fn track_run_stats(
+    mut damage_taken_event_reader: EventReader<DamageTaken>,
+    mut run_stats: ResMut<RunStats>,
+    projectile_query: Query<&Projectile, Added<Projectile>>,
+    asteroid_query: Query<&Health, With<Asteroid>>,
+    player_query: Query<(), With<Player>>,
+) {
+    run_stats.shots_fired += projectile_query
+        .iter()
+        .filter(|projectile| projectile.player.is_some())
+        .count() as u32;
+
+    // a shot can hit more than one thing on the way out, but only counts once
+    let mut hits = HashSet::new();
+    for &DamageTaken {
+        entity,
+        player,
+        projectile,
+        amount,
+    } in damage_taken_event_reader.read()
+    {
+        if player_query.contains(entity) {
+            run_stats.damage_taken += amount;
+        }
+        if let Some(projectile) = projectile.filter(|_| player.is_some()) {
+            hits.insert(projectile);
+        }
+    }
+    run_stats.shots_hit += hits.len() as u32;
+
+    // despawn_dead_entities removes them at the end of this set, so each one counts once
+    run_stats.asteroids_destroyed += asteroid_query
+        .iter()
+        .filter(|health| health.value <= 0.0 && health.last_hit_by.is_some())
+        .count() as u32;
+}
+
+fn start_slow_motion(
+    mut commands: Commands,
+    mut run_over_event_reader: EventReader<RunOver>,
+    mut time: ResMut<Time<Virtual>>,
+    slow_motion: Option<Res<SlowMotion>>,
+) {
+    if run_over_event_reader.read().count() == 0 || slow_motion.is_some() {
+        return;
+    }
+    time.set_relative_speed(SLOW_MOTION_SPEED);
+    commands.insert_resource(SlowMotion {
+        timer: Timer::from_seconds(SLOW_MOTION_SECONDS, TimerMode::Once),
+    });
+}
+
+/// Freezes the game once the slow-motion beat is over and moves on to the game over screen.
+fn finish_slow_motion(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    slow_motion: Option<ResMut<SlowMotion>>,
+    real_time: Res<Time<Real>>,
+) {
+    let Some(mut slow_motion) = slow_motion else {
+        return;
+    };
+    if slow_motion.timer.tick(real_time.delta()).finished() {
+        virtual_time.pause();
+        next_state.set(GameState::GameOver);
+    }
+}
+
+pub fn summarize_run(
+    mut commands: Commands,
+    run_stats: Res<RunStats>,
+    score: Res<Score>,
+    run_time: Res<RunTime>,
+    wave_state: Res<WaveState>,
+    path: Res<RunSummaryPath>,
+) {
+    let summary = RunSummary {
+        score: score.total(),
+        wave: wave_state.wave + 1,
+        seconds_survived: run_time.elapsed.as_secs_f32(),
+        asteroids_destroyed: run_stats.asteroids_destroyed,
+        shots_fired: run_stats.shots_fired,
+        accuracy: if run_stats.shots_fired > 0 {
+            run_stats.shots_hit as f32 / run_stats.shots_fired as f32
+        } else {
+            0.0
+        },
+        damage_taken: run_stats.damage_taken,
+    };
+    info!("run over: {:?}", summary);
+    if let Some(path) = &path.0 {
+        summary.save(path);
+    }
+    commands.insert_resource(summary);
+}
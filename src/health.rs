@@ -1,12 +1,96 @@
 use bevy::prelude::*;
 
+use crate::player::Player;
+use crate::schedule::InGameSet;
+
 #[derive(Component, Debug)]
 pub struct Health {
     pub value: f32,
+    /// The player who dealt the latest damage, with projectiles credited to whoever fired them,
+    /// or `None` if it wasn't a player.
+    pub last_hit_by: Option<Player>,
 }
 
 impl Health {
     pub fn new(value: f32) -> Self {
-        Self { value }
+        Self {
+            value,
+            last_hit_by: None,
+        }
+    }
+}
+
+/// Ignores all collision damage until the timer runs out, then removes itself.
+#[derive(Component, Debug)]
+pub struct Invulnerable {
+    pub timer: Timer,
+}
+
+impl Invulnerable {
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+
+    /// Makes sure at least `seconds` are left, never cutting short protection already running.
+    pub fn extend(&mut self, seconds: f32) {
+        if self.timer.remaining_secs() < seconds {
+            *self = Self::new(seconds);
+        }
+    }
+}
+
+/// Ignores collision damage from projectiles only, e.g. while evading with a barrel roll.
+#[derive(Component, Debug)]
+pub struct ProjectileImmunity {
+    pub timer: Timer,
+}
+
+impl ProjectileImmunity {
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+}
+
+/// A component that removes itself from its entity once its timer finishes.
+pub trait Expiring: Component {
+    fn timer_mut(&mut self) -> &mut Timer;
+}
+
+impl Expiring for Invulnerable {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+impl Expiring for ProjectileImmunity {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (expire::<Invulnerable>, expire::<ProjectileImmunity>).in_set(InGameSet::EntityUpdates),
+        );
+    }
+}
+
+pub fn expire<T: Expiring>(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut T)>,
+    time: Res<Time>,
+) {
+    for (entity, mut component) in query.iter_mut() {
+        if component.timer_mut().tick(time.delta()).finished() {
+            commands.entity(entity).remove::<T>();
+        }
     }
 }
@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::path::PathBuf;
+
+use crate::player::MatchMode;
+use crate::rng::GameRng;
+use crate::save_file::{self, Format};
+use crate::score::Score;
+use crate::settings::Settings;
+use crate::ship_definition::ShipDefinition;
+use crate::ship_selection::SelectedShips;
+use crate::state::{GameState, RunTime};
+use crate::wave::WaveState;
+
+const HIGH_SCORES_FILE: &str = "high_scores.ron";
+/// How many entries each table keeps.
+const TABLE_SIZE: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: u32,
+    /// Counting from 1, as shown to the player.
+    pub wave: u32,
+    pub duration_seconds: f32,
+    pub ship: String,
+    pub seed: u64,
+}
+
+/// The best runs played on this machine, with a table for each match mode, saved whenever a run
+/// makes it in.
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScores {
+    pub tables: HashMap<MatchMode, Vec<HighScoreEntry>>,
+}
+
+impl HighScores {
+    /// Best first.
+    pub fn table(&self, match_mode: MatchMode) -> &[HighScoreEntry] {
+        self.tables.get(&match_mode).map_or(&[], Vec::as_slice)
+    }
+
+    /// Adds `entry` if it's good enough to make the table, returning its place counting from 0.
+    /// Ties go to whoever got there first.
+    pub fn insert(&mut self, match_mode: MatchMode, entry: HighScoreEntry) -> Option<usize> {
+        let table = self.tables.entry(match_mode).or_default();
+        let rank = table
+            .iter()
+            .position(|existing| entry.score > existing.score)
+            .unwrap_or(table.len());
+        if rank >= TABLE_SIZE {
+            return None;
+        }
+        table.insert(rank, entry);
+        table.truncate(TABLE_SIZE);
+        Some(rank)
+    }
+
+    /// Loads the saved tables, starting afresh if there are none. Tables that can't be read are
+    /// backed up rather than saved over.
+    pub fn load() -> Self {
+        let Some(path) = high_scores_path() else {
+            return Self::default();
+        };
+        let Some(mut high_scores) =
+            save_file::load(&path, |contents| ron::from_str::<Self>(contents))
+        else {
+            return Self::default();
+        };
+        // the file may have been edited by hand
+        for table in high_scores.tables.values_mut() {
+            table.sort_by_key(|entry| Reverse(entry.score));
+            table.truncate(TABLE_SIZE);
+        }
+        high_scores
+    }
+
+    pub fn save(&self) {
+        let Some(path) = high_scores_path() else {
+            return;
+        };
+        save_file::save(self, &path, Format::Ron);
+    }
+}
+
+fn high_scores_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(HIGH_SCORES_FILE))
+}
+
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load())
+            .add_systems(OnEnter(GameState::GameOver), record_high_scores);
+    }
+}
+
+/// Enters each player's score for the run that just ended into the table for its match mode.
+fn record_high_scores(
+    mut high_scores: ResMut<HighScores>,
+    (selected_ships, definitions): (Res<SelectedShips>, Res<Assets<ShipDefinition>>),
+    score: Res<Score>,
+    wave_state: Res<WaveState>,
+    run_time: Res<RunTime>,
+    rng: Res<GameRng>,
+    settings: Res<Settings>,
+) {
+    let mut changed = false;
+    for player in settings.players() {
+        let Some(player_score) = score.players.get(&player).filter(|score| score.value > 0) else {
+            continue;
+        };
+        let ship = selected_ships
+            .ships
+            .get(&player)
+            .and_then(|handle| definitions.get(handle))
+            .map_or_else(String::new, |definition| definition.name.clone());
+        let entry = HighScoreEntry {
+            name: settings.player_name(player),
+            score: player_score.value,
+            wave: wave_state.wave + 1,
+            duration_seconds: run_time.elapsed.as_secs_f32(),
+            ship,
+            seed: rng.seed(),
+        };
+        if let Some(rank) = high_scores.insert(settings.match_mode, entry) {
+            info!(
+                "{} placed {} in the {:?} high scores",
+                settings.player_name(player),
+                rank + 1,
+                settings.match_mode
+            );
+            changed = true;
+        }
+    }
+    if changed {
+        high_scores.save();
+    }
+}
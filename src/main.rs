@@ -1,26 +1,73 @@
+mod ability;
+mod action;
 mod asset_loader;
 mod asteroid;
+mod boss;
 mod camera;
 mod collision;
+mod config;
+mod controls;
 mod debug;
 mod despawn;
+mod enemy;
+mod faction;
+mod game_over;
 mod health;
+mod high_score;
+mod menu;
 mod movement;
+mod player;
+mod powerup;
+mod progression;
+mod rng;
+mod save_file;
 mod schedule;
+mod score;
+mod settings;
+mod shield;
+mod ship_definition;
+mod ship_selection;
 mod spaceship;
+mod spatial;
 mod state;
+mod steering;
+mod structure;
+mod wave;
+mod weapon;
 
+use ability::AbilityPlugin;
+use action::ActionPlugin;
 use asset_loader::AssetLoaderPlugin;
 use asteroid::AsteroidPlugin;
 use bevy::prelude::*;
+use boss::BossPlugin;
 use camera::CameraPlugin;
 use collision::CollisionPlugin;
+use controls::ControlsPlugin;
 use debug::DebugPlugin;
 use despawn::DespawnPlugin;
+use enemy::EnemyPlugin;
+use faction::FactionPlugin;
+use game_over::GameOverPlugin;
+use health::HealthPlugin;
+use high_score::HighScorePlugin;
+use menu::MenuPlugin;
 use movement::MovementPlugin;
+use powerup::PowerUpPlugin;
+use progression::ProgressionPlugin;
+use rng::RngPlugin;
 use schedule::SchedulePlugin;
+use score::ScorePlugin;
+use settings::SettingsPlugin;
+use shield::ShieldPlugin;
+use ship_definition::ShipDefinitionPlugin;
+use ship_selection::ShipSelectionPlugin;
 use spaceship::SpaceshipPlugin;
 use state::StatePlugin;
+use steering::SteeringPlugin;
+use structure::StructurePlugin;
+use wave::WavePlugin;
+use weapon::WeaponPlugin;
 
 fn main() {
     App::new()
@@ -32,13 +79,35 @@ fn main() {
         .add_plugins(DefaultPlugins)
         .add_plugins(DebugPlugin { enabled: true })
         .add_plugins(AssetLoaderPlugin)
+        .add_plugins(SettingsPlugin)
+        .add_plugins(ActionPlugin)
         .add_plugins(StatePlugin)
+        .add_plugins(RngPlugin)
+        .add_plugins(MenuPlugin)
+        .add_plugins(ShipDefinitionPlugin)
+        .add_plugins(ShipSelectionPlugin)
+        .add_plugins(ProgressionPlugin)
+        .add_plugins(ControlsPlugin)
         .add_plugins(DespawnPlugin)
         .add_plugins(CameraPlugin)
         .add_plugins(CollisionPlugin)
+        .add_plugins(FactionPlugin)
+        .add_plugins(HealthPlugin)
+        .add_plugins(ShieldPlugin)
         .add_plugins(MovementPlugin)
+        .add_plugins(SteeringPlugin)
         .add_plugins(AsteroidPlugin)
         .add_plugins(SpaceshipPlugin)
+        .add_plugins(EnemyPlugin)
+        .add_plugins(BossPlugin)
+        .add_plugins(StructurePlugin)
+        .add_plugins(WavePlugin)
+        .add_plugins(ScorePlugin)
+        .add_plugins(HighScorePlugin)
+        .add_plugins(GameOverPlugin)
+        .add_plugins(WeaponPlugin)
+        .add_plugins(AbilityPlugin)
+        .add_plugins(PowerUpPlugin)
         .add_plugins(SchedulePlugin)
         .run();
 }
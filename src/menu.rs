@@ -0,0 +1,632 @@
+use bevy::app::AppExit;
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+
+use crate::action::{Action, ActionMap, ActionState, Binding, PendingRebind};
+use crate::asset_loader::SceneAssets;
+use crate::config::ConfigFiles;
+use crate::game_over::{summarize_run, RunSummary};
+use crate::high_score::{HighScoreEntry, HighScores};
+use crate::player::{MatchMode, Player};
+use crate::progression::RunCredits;
+use crate::settings::Settings;
+use crate::ship_definition::ShipClasses;
+use crate::state::{GameState, RestartRun};
+
+const TITLE: &str = "Spaceship Game";
+const TITLE_SIZE: f32 = 48.0;
+const BODY_SIZE: f32 = 20.0;
+const OPTION_SIZE: f32 = 28.0;
+const SELECTED_COLOR: Color = Color::rgb(1.0, 0.8, 0.2);
+
+/// Something that can be picked from a menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuOption {
+    Start,
+    /// Cycles how many players share the machine, on the main menu so it's set before a run.
+    PlayerCount,
+    MatchMode,
+    HighScores,
+    PlayAgain,
+    Resume,
+    Restart,
+    Settings,
+    ControlScheme,
+    Bindings,
+    /// Waits for a new key or button for the action, on the bindings page of the pause menu.
+    Rebind(Action),
+    NextPlayer,
+    Back,
+    MainMenu,
+    QuitToMenu,
+    Quit,
+}
+
+impl MenuOption {
+    fn label(&self) -> &'static str {
+        match self {
+            MenuOption::Start => "Start",
+            MenuOption::PlayerCount => "Players",
+            MenuOption::MatchMode => "Match mode",
+            MenuOption::HighScores => "High scores",
+            MenuOption::PlayAgain => "Play again",
+            MenuOption::Resume => "Resume",
+            MenuOption::Restart => "Restart",
+            MenuOption::Settings => "Settings",
+            MenuOption::ControlScheme => "Change control scheme",
+            MenuOption::Bindings => "Bindings",
+            MenuOption::Rebind(_) => "Rebind",
+            MenuOption::NextPlayer => "Next player",
+            MenuOption::Back => "Back",
+            MenuOption::MainMenu => "Main menu",
+            MenuOption::QuitToMenu => "Quit to menu",
+            MenuOption::Quit => "Quit",
+        }
+    }
+}
+
+/// A column of options under a title. Any player browses and picks with the menu actions.
+#[derive(Component, Debug)]
+pub struct Menu {
+    pub options: Vec<MenuOption>,
+    pub selected: usize,
+}
+
+impl Menu {
+    pub fn new(options: Vec<MenuOption>) -> Self {
+        Self {
+            options,
+            selected: 0,
+        }
+    }
+}
+
+#[derive(Event, Debug)]
+pub struct MenuOptionChosen {
+    pub option: MenuOption,
+}
+
+impl MenuOptionChosen {
+    pub fn new(option: MenuOption) -> Self {
+        Self { option }
+    }
+}
+
+#[derive(Component, Debug)]
+struct LoadingScreen;
+
+#[derive(Component, Debug)]
+struct MainMenuScreen;
+
+#[derive(Component, Debug)]
+struct HighScoreScreen;
+
+/// Which page of the pause menu is showing. They all stay within `GameState::Paused`.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum PausePage {
+    Main,
+    /// Only settings that can change in the middle of a run are offered here.
+    Settings,
+    /// One player's bindings, each replaced by picking it and pressing something new.
+    Bindings(Player),
+}
+
+#[derive(Component, Debug)]
+struct GameOverScreen;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MenuOptionChosen>()
+            .add_systems(OnEnter(GameState::Loading), spawn_loading_screen)
+            .add_systems(Update, finish_loading.run_if(in_state(GameState::Loading)))
+            .add_systems(OnExit(GameState::Loading), despawn_screen::<LoadingScreen>)
+            .add_systems(OnEnter(GameState::MainMenu), spawn_main_menu)
+            .add_systems(
+                OnExit(GameState::MainMenu),
+                despawn_screen::<MainMenuScreen>,
+            )
+            .add_systems(OnEnter(GameState::HighScores), spawn_high_score_screen)
+            .add_systems(
+                OnExit(GameState::HighScores),
+                despawn_screen::<HighScoreScreen>,
+            )
+            .add_systems(OnEnter(GameState::Paused), spawn_pause_menu)
+            .add_systems(OnExit(GameState::Paused), despawn_screen::<PausePage>)
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                spawn_game_over_screen.after(summarize_run),
+            )
+            .add_systems(
+                OnExit(GameState::GameOver),
+                despawn_screen::<GameOverScreen>,
+            )
+            .add_systems(
+                Update,
+                (
+                    navigate_menus,
+                    (
+                        choose_menu_options,
+                        switch_pause_pages.run_if(in_state(GameState::Paused)),
+                        change_match_settings.run_if(in_state(GameState::MainMenu)),
+                    ),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                show_new_bindings.run_if(
+                    in_state(GameState::Paused).and_then(resource_changed::<PendingRebind>),
+                ),
+            );
+    }
+}
+
+/// Spawns a menu in the middle of the screen, with any `body` text between the title and the
+/// options, tagged with `screen` so it can be torn down with the rest of that screen.
+pub fn spawn_menu(
+    commands: &mut Commands,
+    title: String,
+    body: String,
+    options: Vec<MenuOption>,
+    screen: impl Bundle,
+) {
+    let labels = options
+        .iter()
+        .map(|option| option.label().to_string())
+        .collect();
+    spawn_labelled_menu(commands, title, body, Menu::new(options), labels, screen);
+}
+
+/// Like `spawn_menu`, but with a label given for each of the menu's options, for options that
+/// show more than their name.
+fn spawn_labelled_menu(
+    commands: &mut Commands,
+    title: String,
+    body: String,
+    menu: Menu,
+    labels: Vec<String>,
+    screen: impl Bundle,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                ..default()
+            },
+            screen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    title,
+                    TextStyle {
+                        font_size: TITLE_SIZE,
+                        ..default()
+                    },
+                )
+                .with_text_justify(JustifyText::Center),
+            );
+            if !body.is_empty() {
+                parent.spawn(
+                    TextBundle::from_section(
+                        body,
+                        TextStyle {
+                            font_size: BODY_SIZE,
+                            ..default()
+                        },
+                    )
+                    .with_text_justify(JustifyText::Center),
+                );
+            }
+            let sections = labels.into_iter().map(|label| {
+                TextSection::new(
+                    format!("{}\n", label),
+                    TextStyle {
+                        font_size: OPTION_SIZE,
+                        ..default()
+                    },
+                )
+            });
+            parent.spawn((
+                TextBundle::from_sections(sections).with_text_justify(JustifyText::Center),
+                menu,
+            ));
+        });
+}
+
+fn despawn_screen<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn spawn_loading_screen(mut commands: Commands) {
+    spawn_menu(
+        &mut commands,
+        "Loading...".to_string(),
+        String::new(),
+        vec![],
+        LoadingScreen,
+    );
+}
+
+/// Moves on to the main menu once every asset needed to play has loaded, or failed to.
+fn finish_loading(
+    mut next_state: ResMut<NextState<GameState>>,
+    asset_server: Res<AssetServer>,
+    scene_assets: Res<SceneAssets>,
+    ship_classes: Res<ShipClasses>,
+    config_files: Res<ConfigFiles>,
+) {
+    let ids = [
+        scene_assets.asteroid.id().untyped(),
+        scene_assets.enemy.id().untyped(),
+        scene_assets.missile.id().untyped(),
+        ship_classes.folder.id().untyped(),
+    ];
+    let config_ids = config_files.handles.iter().map(|handle| handle.id());
+    let done = ids.into_iter().chain(config_ids).all(|id| {
+        asset_server.is_loaded_with_dependencies(id)
+            || asset_server.load_state(id) == LoadState::Failed
+    });
+    if done {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+fn spawn_main_menu(mut commands: Commands, settings: Res<Settings>) {
+    spawn_main_menu_screen(&mut commands, 0, &settings);
+}
+
+fn spawn_main_menu_screen(commands: &mut Commands, selected: usize, settings: &Settings) {
+    let options = vec![
+        MenuOption::Start,
+        MenuOption::PlayerCount,
+        MenuOption::MatchMode,
+        MenuOption::HighScores,
+        MenuOption::Quit,
+    ];
+    let labels = options
+        .iter()
+        .map(|option| match option {
+            MenuOption::PlayerCount => format!("Players: {}", settings.player_count()),
+            MenuOption::MatchMode => format!("Match mode: {}", settings.match_mode.label()),
+            _ => option.label().to_string(),
+        })
+        .collect();
+    spawn_labelled_menu(
+        commands,
+        TITLE.to_string(),
+        String::new(),
+        Menu { options, selected },
+        labels,
+        MainMenuScreen,
+    );
+}
+
+fn spawn_high_score_screen(mut commands: Commands, high_scores: Res<HighScores>) {
+    let mut lines = vec![];
+    for match_mode in [MatchMode::Coop, MatchMode::Versus] {
+        lines.push(format!("{:?}", match_mode));
+        let table = high_scores.table(match_mode);
+        if table.is_empty() {
+            lines.push("No scores yet".to_string());
+        }
+        for (rank, entry) in table.iter().enumerate() {
+            lines.push(high_score_line(rank, entry));
+        }
+        lines.push(String::new());
+    }
+    spawn_menu(
+        &mut commands,
+        "High scores".to_string(),
+        lines.join("\n"),
+        vec![MenuOption::MainMenu],
+        HighScoreScreen,
+    );
+}
+
+fn high_score_line(rank: usize, entry: &HighScoreEntry) -> String {
+    let seconds = entry.duration_seconds as u32;
+    format!(
+        "{}. {}  {}  wave {}  {}:{:02}  {}  seed {}",
+        rank + 1,
+        entry.name,
+        entry.score,
+        entry.wave,
+        seconds / 60,
+        seconds % 60,
+        entry.ship,
+        entry.seed,
+    )
+}
+
+fn spawn_pause_menu(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    action_map: Res<ActionMap>,
+    pending_rebind: Res<PendingRebind>,
+) {
+    spawn_pause_page(
+        &mut commands,
+        PausePage::Main,
+        0,
+        &settings,
+        &action_map,
+        &pending_rebind,
+    );
+}
+
+fn spawn_pause_page(
+    commands: &mut Commands,
+    page: PausePage,
+    selected: usize,
+    settings: &Settings,
+    action_map: &ActionMap,
+    pending_rebind: &PendingRebind,
+) {
+    let (title, body, options) = match page {
+        PausePage::Main => (
+            "Paused".to_string(),
+            String::new(),
+            vec![
+                MenuOption::Resume,
+                MenuOption::Restart,
+                MenuOption::Settings,
+                MenuOption::QuitToMenu,
+            ],
+        ),
+        PausePage::Settings => (
+            "Settings".to_string(),
+            format!("Control scheme: {}", settings.control_scheme.label()),
+            vec![
+                MenuOption::ControlScheme,
+                MenuOption::Bindings,
+                MenuOption::Back,
+            ],
+        ),
+        PausePage::Bindings(player) => {
+            let mut options: Vec<_> = Action::ALL.into_iter().map(MenuOption::Rebind).collect();
+            if settings.player_count() > 1 {
+                options.push(MenuOption::NextPlayer);
+            }
+            options.push(MenuOption::Back);
+            let body = match pending_rebind.0 {
+                Some((_, action)) => format!(
+                    "Press the new key or button for {:?}, or pause to cancel",
+                    action
+                ),
+                None => "Pick an action to rebind it".to_string(),
+            };
+            (
+                format!("{} bindings", settings.player_name(player)),
+                body,
+                options,
+            )
+        }
+    };
+    let labels = options
+        .iter()
+        .map(|option| match (page, option) {
+            (PausePage::Bindings(player), &MenuOption::Rebind(action)) => {
+                let bindings = action_map
+                    .player(player)
+                    .map_or(&[][..], |player_bindings| player_bindings.bindings(action));
+                let bindings: Vec<String> = bindings.iter().map(Binding::label).collect();
+                format!("{:?}: {}", action, bindings.join(", "))
+            }
+            _ => option.label().to_string(),
+        })
+        .collect();
+    spawn_labelled_menu(
+        commands,
+        title,
+        body,
+        Menu { options, selected },
+        labels,
+        page,
+    );
+}
+
+fn spawn_game_over_screen(
+    mut commands: Commands,
+    summary: Res<RunSummary>,
+    run_credits: Res<RunCredits>,
+) {
+    let seconds = summary.seconds_survived as u32;
+    let body = format!(
+        "Scored {}\nReached wave {} in {}:{:02}\n{} asteroids destroyed\n{} shots fired, {:.0}% on target\n{:.0} damage taken\nEarned {} credits",
+        summary.score,
+        summary.wave,
+        seconds / 60,
+        seconds % 60,
+        summary.asteroids_destroyed,
+        summary.shots_fired,
+        summary.accuracy * 100.0,
+        summary.damage_taken,
+        run_credits.value
+    );
+    spawn_menu(
+        &mut commands,
+        "Game over".to_string(),
+        body,
+        vec![
+            MenuOption::PlayAgain,
+            MenuOption::MainMenu,
+            MenuOption::Quit,
+        ],
+        GameOverScreen,
+    );
+}
+
+fn navigate_menus(
+    mut menu_option_chosen_event_writer: EventWriter<MenuOptionChosen>,
+    mut query: Query<(&mut Menu, &mut Text)>,
+    action_state: Res<ActionState>,
+) {
+    for (mut menu, mut text) in query.iter_mut() {
+        let count = menu.options.len();
+        if count == 0 {
+            continue;
+        }
+        if action_state.any_just_pressed(Action::MenuUp) {
+            menu.selected = (menu.selected + count - 1) % count;
+        }
+        if action_state.any_just_pressed(Action::MenuDown) {
+            menu.selected = (menu.selected + 1) % count;
+        }
+        if action_state.any_just_pressed(Action::MenuSelect) {
+            menu_option_chosen_event_writer
+                .send(MenuOptionChosen::new(menu.options[menu.selected]));
+        }
+
+        for (index, section) in text.sections.iter_mut().enumerate() {
+            section.style.color = if index == menu.selected {
+                SELECTED_COLOR
+            } else {
+                Color::WHITE
+            };
+        }
+    }
+}
+
+fn choose_menu_options(
+    mut menu_option_chosen_event_reader: EventReader<MenuOptionChosen>,
+    mut app_exit_event_writer: EventWriter<AppExit>,
+    mut restart_run_event_writer: EventWriter<RestartRun>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for &MenuOptionChosen { option } in menu_option_chosen_event_reader.read() {
+        match option {
+            MenuOption::Start => next_state.set(GameState::ShipSelection),
+            MenuOption::HighScores => next_state.set(GameState::HighScores),
+            // with the same ships, straight back into a fresh run
+            MenuOption::PlayAgain => next_state.set(GameState::InGame),
+            MenuOption::Resume => next_state.set(GameState::InGame),
+            MenuOption::Restart => {
+                restart_run_event_writer.send(RestartRun);
+            }
+            MenuOption::MainMenu | MenuOption::QuitToMenu => next_state.set(GameState::MainMenu),
+            MenuOption::Quit => {
+                app_exit_event_writer.send(AppExit);
+            }
+            // settings on the main menu, see change_match_settings
+            MenuOption::PlayerCount | MenuOption::MatchMode => (),
+            // pages of the pause menu, see switch_pause_pages
+            MenuOption::Settings
+            | MenuOption::ControlScheme
+            | MenuOption::Bindings
+            | MenuOption::Rebind(_)
+            | MenuOption::NextPlayer
+            | MenuOption::Back => (),
+        }
+    }
+}
+
+/// Changes the player count and match mode from the main menu, redrawing it to show them.
+fn change_match_settings(
+    mut commands: Commands,
+    mut menu_option_chosen_event_reader: EventReader<MenuOptionChosen>,
+    mut settings: ResMut<Settings>,
+    screen_query: Query<Entity, With<MainMenuScreen>>,
+    menu_query: Query<&Menu>,
+) {
+    for &MenuOptionChosen { option } in menu_option_chosen_event_reader.read() {
+        match option {
+            MenuOption::PlayerCount => settings.local_players = settings.next_player_count(),
+            MenuOption::MatchMode => settings.match_mode = settings.match_mode.next(),
+            _ => continue,
+        }
+        let Ok(entity) = screen_query.get_single() else {
+            continue;
+        };
+        let selected = menu_query.get_single().map_or(0, |menu| menu.selected);
+        commands.entity(entity).despawn_recursive();
+        spawn_main_menu_screen(&mut commands, selected, &settings);
+    }
+}
+
+/// Moves between the pages of the pause menu, redrawing the current one when it changes.
+fn switch_pause_pages(
+    mut commands: Commands,
+    mut menu_option_chosen_event_reader: EventReader<MenuOptionChosen>,
+    mut settings: ResMut<Settings>,
+    mut pending_rebind: ResMut<PendingRebind>,
+    page_query: Query<(Entity, &PausePage)>,
+    menu_query: Query<&Menu>,
+    action_map: Res<ActionMap>,
+) {
+    for &MenuOptionChosen { option } in menu_option_chosen_event_reader.read() {
+        let Ok((entity, &page)) = page_query.get_single() else {
+            continue;
+        };
+        let selected = menu_query.get_single().map_or(0, |menu| menu.selected);
+        let (next_page, selected) = match (page, option) {
+            (PausePage::Main, MenuOption::Settings) => (PausePage::Settings, 0),
+            (PausePage::Settings, MenuOption::ControlScheme) => {
+                settings.control_scheme = settings.control_scheme.next();
+                (page, selected)
+            }
+            (PausePage::Settings, MenuOption::Bindings) => (PausePage::Bindings(Player(0)), 0),
+            (PausePage::Settings, MenuOption::Back) => (PausePage::Main, 0),
+            (PausePage::Bindings(player), MenuOption::Rebind(action)) => {
+                // capture_rebind picks up the next thing pressed, and show_new_bindings redraws
+                pending_rebind.0 = Some((player, action));
+                (page, selected)
+            }
+            (PausePage::Bindings(player), MenuOption::NextPlayer) => {
+                let next = Player((player.0 + 1) % settings.player_count());
+                (PausePage::Bindings(next), 0)
+            }
+            (PausePage::Bindings(_), MenuOption::Back) => (PausePage::Settings, 0),
+            _ => continue,
+        };
+        commands.entity(entity).despawn_recursive();
+        spawn_pause_page(
+            &mut commands,
+            next_page,
+            selected,
+            &settings,
+            &action_map,
+            &pending_rebind,
+        );
+    }
+}
+
+/// Redraws the bindings page once a rebind has been captured or cancelled. Starting one is
+/// redrawn by switch_pause_pages.
+fn show_new_bindings(
+    mut commands: Commands,
+    page_query: Query<(Entity, &PausePage)>,
+    menu_query: Query<&Menu>,
+    settings: Res<Settings>,
+    action_map: Res<ActionMap>,
+    pending_rebind: Res<PendingRebind>,
+) {
+    let Ok((entity, &page)) = page_query.get_single() else {
+        return;
+    };
+    if !matches!(page, PausePage::Bindings(_)) || pending_rebind.0.is_some() {
+        return;
+    }
+    let selected = menu_query.get_single().map_or(0, |menu| menu.selected);
+    commands.entity(entity).despawn_recursive();
+    spawn_pause_page(
+        &mut commands,
+        page,
+        selected,
+        &settings,
+        &action_map,
+        &pending_rebind,
+    );
+}
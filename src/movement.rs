@@ -46,6 +46,12 @@ pub struct MovingObjectBundle {
     pub model: SceneBundle,
 }
 
+/// The right-hand side of an entity facing along its local +Z on the gameplay plane, unaffected
+/// by any roll around that axis.
+pub fn planar_right(transform: &Transform) -> Vec3 {
+    (-transform.forward()).cross(Vec3::Y)
+}
+
 fn update_velocity(mut query: Query<(&Acceleration, &mut Velocity)>, time: Res<Time>) {
     for (accleration, mut velocity) in query.iter_mut() {
         velocity.value += accleration.value * time.delta_seconds();
@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How many players can share one machine.
+pub const MAX_LOCAL_PLAYERS: u8 = 4;
+
+const PLAYER_COLORS: [Color; 4] = [
+    Color::rgb(0.2, 0.6, 1.0),
+    Color::rgb(1.0, 0.4, 0.2),
+    Color::rgb(0.3, 1.0, 0.4),
+    Color::rgb(1.0, 0.9, 0.2),
+];
+
+/// A local player, numbered from 0. Also indexes that player's bindings in the `ActionMap`.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Player(pub u8);
+
+impl Player {
+    pub fn color(&self) -> Color {
+        PLAYER_COLORS[self.0 as usize % PLAYER_COLORS.len()]
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MatchMode {
+    /// Players fight on the same side and the run ends when everyone is out of lives.
+    #[default]
+    Coop,
+    /// Players can shoot each other and the run ends when one player is left standing.
+    Versus,
+}
+
+impl MatchMode {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MatchMode::Coop => "Co-op",
+            MatchMode::Versus => "Versus",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            MatchMode::Coop => MatchMode::Versus,
+            MatchMode::Versus => MatchMode::Coop,
+        }
+    }
+}
@@ -0,0 +1,410 @@
+use bevy::prelude::*;
+use rand::Rng;
+use std::ops::Range;
+
+use crate::asteroid::Asteroid;
+use crate::collision::Collider;
+use crate::despawn::{DespawnOnRunEnd, DespawnWhenRemote};
+use crate::health::{Expiring, Health};
+use crate::movement::{Acceleration, Velocity};
+use crate::rng::GameRng;
+use crate::schedule::InGameSet;
+use crate::shield::{Shield, ShieldRestored};
+use crate::spaceship::Spaceship;
+use crate::state::OnRunStart;
+use crate::steering::{Steering, SteeringBehaviour};
+
+const RADIUS: f32 = 1.5;
+const VELOCITY_SCALAR: f32 = 2.0;
+const SPAWN_RANGE_X: Range<f32> = -25.0..25.0;
+const SPAWN_RANGE_Z: Range<f32> = 0.0..25.0;
+const SPAWN_TIME_SECONDS: f32 = 12.0;
+const DROP_CHANCE: f64 = 0.15;
+const SPIN_SPEED: f32 = 1.5;
+const WANDER: SteeringBehaviour = SteeringBehaviour::Wander {
+    distance: 4.0,
+    radius: 2.0,
+    jitter: 3.0,
+};
+const WANDER_ACCELERATION: f32 = 2.0;
+const RAPID_FIRE_SECONDS: f32 = 8.0;
+const RAPID_FIRE_MULTIPLIER: f32 = 2.5;
+const TRIPLE_SHOT_SECONDS: f32 = 10.0;
+const SHIELD_RECHARGE_SECONDS: f32 = 3.0;
+const SHIELD_RECHARGE_RATE: f32 = 25.0;
+const SPEED_BOOST_SECONDS: f32 = 8.0;
+const SPEED_BOOST_MULTIPLIER: f32 = 1.4;
+const INVINCIBILITY_SECONDS: f32 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerUpKind {
+    RapidFire,
+    TripleShot,
+    ShieldRecharge,
+    SpeedBoost,
+    Invincibility,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 5] = [
+        PowerUpKind::RapidFire,
+        PowerUpKind::TripleShot,
+        PowerUpKind::ShieldRecharge,
+        PowerUpKind::SpeedBoost,
+        PowerUpKind::Invincibility,
+    ];
+
+    fn color(&self) -> Color {
+        match self {
+            PowerUpKind::RapidFire => Color::ORANGE,
+            PowerUpKind::TripleShot => Color::FUCHSIA,
+            PowerUpKind::ShieldRecharge => Color::CYAN,
+            PowerUpKind::SpeedBoost => Color::LIME_GREEN,
+            PowerUpKind::Invincibility => Color::GOLD,
+        }
+    }
+}
+
+/// A pickup, collected by the first spaceship to touch it.
+#[derive(Component, Debug)]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+}
+
+impl PowerUp {
+    pub fn new(kind: PowerUpKind) -> Self {
+        Self { kind }
+    }
+}
+
+/// Shortens weapon cooldowns by ticking them `multiplier` times as fast.
+#[derive(Component, Debug)]
+pub struct RapidFire {
+    pub multiplier: f32,
+    pub timer: Timer,
+}
+
+/// Fires three projectiles in a spread instead of one.
+#[derive(Component, Debug)]
+pub struct TripleShot {
+    pub timer: Timer,
+}
+
+/// Recharges the shield at `rate` per second, regardless of its regen delay.
+#[derive(Component, Debug)]
+pub struct ShieldRecharge {
+    pub rate: f32,
+    pub timer: Timer,
+}
+
+/// Multiplies ship movement speed.
+#[derive(Component, Debug)]
+pub struct SpeedBoost {
+    pub multiplier: f32,
+    pub timer: Timer,
+}
+
+/// Ignores all collision damage, like `Invulnerable`, but kept separate so a dash or respawn
+/// can't cut it short.
+#[derive(Component, Debug)]
+pub struct Invincibility {
+    pub timer: Timer,
+}
+
+/// A timed modifier applied by collecting a power-up.
+pub trait PowerUpEffect: Expiring {
+    const KIND: PowerUpKind;
+}
+
+impl Expiring for RapidFire {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+impl PowerUpEffect for RapidFire {
+    const KIND: PowerUpKind = PowerUpKind::RapidFire;
+}
+
+impl Expiring for TripleShot {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+impl PowerUpEffect for TripleShot {
+    const KIND: PowerUpKind = PowerUpKind::TripleShot;
+}
+
+impl Expiring for ShieldRecharge {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+impl PowerUpEffect for ShieldRecharge {
+    const KIND: PowerUpKind = PowerUpKind::ShieldRecharge;
+}
+
+impl Expiring for SpeedBoost {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+impl PowerUpEffect for SpeedBoost {
+    const KIND: PowerUpKind = PowerUpKind::SpeedBoost;
+}
+
+impl Expiring for Invincibility {
+    fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+impl PowerUpEffect for Invincibility {
+    const KIND: PowerUpKind = PowerUpKind::Invincibility;
+}
+
+#[derive(Event, Debug)]
+pub struct PowerUpCollected {
+    pub entity: Entity,
+    pub kind: PowerUpKind,
+}
+
+impl PowerUpCollected {
+    pub fn new(entity: Entity, kind: PowerUpKind) -> Self {
+        Self { entity, kind }
+    }
+}
+
+#[derive(Event, Debug)]
+pub struct PowerUpExpired {
+    pub entity: Entity,
+    pub kind: PowerUpKind,
+}
+
+impl PowerUpExpired {
+    pub fn new(entity: Entity, kind: PowerUpKind) -> Self {
+        Self { entity, kind }
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct PowerUpSpawnTimer {
+    timer: Timer,
+}
+
+#[derive(Resource, Debug)]
+struct PowerUpAssets {
+    mesh: Handle<Mesh>,
+    materials: Vec<(PowerUpKind, Handle<StandardMaterial>)>,
+}
+
+impl PowerUpAssets {
+    fn material(&self, kind: PowerUpKind) -> Handle<StandardMaterial> {
+        self.materials
+            .iter()
+            .find(|(material_kind, _)| *material_kind == kind)
+            .map(|(_, material)| material.clone())
+            .unwrap_or_default()
+    }
+}
+
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PowerUpSpawnTimer {
+            timer: Timer::from_seconds(SPAWN_TIME_SECONDS, TimerMode::Repeating),
+        })
+        .add_systems(Startup, create_power_up_assets)
+        .add_systems(
+            Update,
+            (
+                spawn_power_ups,
+                spin_power_ups,
+                collect_power_ups,
+                recharge_shields,
+                (
+                    expire_power_up::<RapidFire>,
+                    expire_power_up::<TripleShot>,
+                    expire_power_up::<ShieldRecharge>,
+                    expire_power_up::<SpeedBoost>,
+                    expire_power_up::<Invincibility>,
+                ),
+            )
+                .chain()
+                .in_set(InGameSet::EntityUpdates),
+        )
+        .add_systems(Update, drop_power_ups.in_set(InGameSet::DespawnEntities))
+        .add_systems(OnRunStart, reset_spawn_timer)
+        .add_event::<PowerUpCollected>()
+        .add_event::<PowerUpExpired>();
+    }
+}
+
+fn create_power_up_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(PowerUpAssets {
+        mesh: meshes.add(Cuboid::from_size(Vec3::splat(RADIUS))),
+        materials: PowerUpKind::ALL
+            .into_iter()
+            .map(|kind| {
+                let material = materials.add(StandardMaterial {
+                    base_color: kind.color(),
+                    emissive: kind.color(),
+                    ..default()
+                });
+                (kind, material)
+            })
+            .collect(),
+    });
+}
+
+fn power_up_bundle(
+    power_up_assets: &PowerUpAssets,
+    rng: &mut GameRng,
+    translation: Vec3,
+) -> impl Bundle {
+    let kind = PowerUpKind::ALL[rng.gen_range(0..PowerUpKind::ALL.len())];
+    let velocity = Vec3::new(rng.gen_range(-1.0..1.0), 0., rng.gen_range(-1.0..1.0))
+        .normalize_or_zero()
+        * VELOCITY_SCALAR;
+    (
+        PbrBundle {
+            mesh: power_up_assets.mesh.clone(),
+            material: power_up_assets.material(kind),
+            transform: Transform::from_translation(translation),
+            ..default()
+        },
+        PowerUp::new(kind),
+        Collider::new(RADIUS),
+        Velocity::new(velocity),
+        Acceleration::new(Vec3::ZERO),
+        Steering::new(VELOCITY_SCALAR, WANDER_ACCELERATION).with_behaviour(WANDER, 1.0),
+        DespawnWhenRemote,
+        DespawnOnRunEnd,
+    )
+}
+
+fn reset_spawn_timer(mut spawn_timer: ResMut<PowerUpSpawnTimer>) {
+    spawn_timer.timer.reset();
+}
+
+fn spawn_power_ups(
+    mut commands: Commands,
+    mut spawn_timer: ResMut<PowerUpSpawnTimer>,
+    mut rng: ResMut<GameRng>,
+    power_up_assets: Res<PowerUpAssets>,
+    time: Res<Time>,
+) {
+    spawn_timer.timer.tick(time.delta());
+    if !spawn_timer.timer.just_finished() {
+        return;
+    }
+    let translation = Vec3::new(
+        rng.gen_range(SPAWN_RANGE_X),
+        0.,
+        rng.gen_range(SPAWN_RANGE_Z),
+    );
+    commands.spawn(power_up_bundle(&power_up_assets, &mut rng, translation));
+}
+
+/// Destroyed asteroids sometimes leave a power-up behind.
+fn drop_power_ups(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    query: Query<(&GlobalTransform, &Health), With<Asteroid>>,
+    power_up_assets: Res<PowerUpAssets>,
+) {
+    for (transform, health) in query.iter() {
+        if health.value > 0.0 || !rng.gen_bool(DROP_CHANCE) {
+            continue;
+        }
+        commands.spawn(power_up_bundle(
+            &power_up_assets,
+            &mut rng,
+            transform.translation(),
+        ));
+    }
+}
+
+fn spin_power_ups(mut query: Query<&mut Transform, With<PowerUp>>, time: Res<Time>) {
+    for mut transform in query.iter_mut() {
+        transform.rotate_y(SPIN_SPEED * time.delta_seconds());
+    }
+}
+
+fn collect_power_ups(
+    mut commands: Commands,
+    mut power_up_collected_event_writer: EventWriter<PowerUpCollected>,
+    power_up_query: Query<(Entity, &PowerUp, &Collider)>,
+    spaceship_query: Query<(), With<Spaceship>>,
+) {
+    for (power_up_entity, power_up, collider) in power_up_query.iter() {
+        let Some(&ship) = collider
+            .colliding_entities
+            .iter()
+            .find(|&&entity| spaceship_query.contains(entity))
+        else {
+            continue;
+        };
+
+        let mut ship_commands = commands.entity(ship);
+        match power_up.kind {
+            PowerUpKind::RapidFire => ship_commands.insert(RapidFire {
+                multiplier: RAPID_FIRE_MULTIPLIER,
+                timer: Timer::from_seconds(RAPID_FIRE_SECONDS, TimerMode::Once),
+            }),
+            PowerUpKind::TripleShot => ship_commands.insert(TripleShot {
+                timer: Timer::from_seconds(TRIPLE_SHOT_SECONDS, TimerMode::Once),
+            }),
+            PowerUpKind::ShieldRecharge => ship_commands.insert(ShieldRecharge {
+                rate: SHIELD_RECHARGE_RATE,
+                timer: Timer::from_seconds(SHIELD_RECHARGE_SECONDS, TimerMode::Once),
+            }),
+            PowerUpKind::SpeedBoost => ship_commands.insert(SpeedBoost {
+                multiplier: SPEED_BOOST_MULTIPLIER,
+                timer: Timer::from_seconds(SPEED_BOOST_SECONDS, TimerMode::Once),
+            }),
+            PowerUpKind::Invincibility => ship_commands.insert(Invincibility {
+                timer: Timer::from_seconds(INVINCIBILITY_SECONDS, TimerMode::Once),
+            }),
+        };
+        commands.entity(power_up_entity).despawn_recursive();
+        power_up_collected_event_writer.send(PowerUpCollected::new(ship, power_up.kind));
+    }
+}
+
+fn recharge_shields(
+    mut shield_restored_event_writer: EventWriter<ShieldRestored>,
+    mut query: Query<(Entity, &mut Shield, &ShieldRecharge)>,
+    time: Res<Time>,
+) {
+    for (entity, mut shield, recharge) in query.iter_mut() {
+        let was_depleted = shield.is_depleted();
+        shield.current = (shield.current + recharge.rate * time.delta_seconds()).min(shield.max);
+        if was_depleted && !shield.is_depleted() {
+            shield_restored_event_writer.send(ShieldRestored::new(entity));
+        }
+    }
+}
+
+/// Like `health::expire`, but also announces the end of the power-up.
+fn expire_power_up<T: PowerUpEffect>(
+    mut commands: Commands,
+    mut power_up_expired_event_writer: EventWriter<PowerUpExpired>,
+    mut query: Query<(Entity, &mut T)>,
+    time: Res<Time>,
+) {
+    for (entity, mut effect) in query.iter_mut() {
+        if effect.timer_mut().tick(time.delta()).finished() {
+            commands.entity(entity).remove::<T>();
+            power_up_expired_event_writer.send(PowerUpExpired::new(entity, T::KIND));
+        }
+    }
+}
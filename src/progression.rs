@@ -0,0 +1,365 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::de::value::{Error as ValueError, StrDeserializer};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::action::{Action, ActionState};
+use crate::asteroid::Asteroid;
+use crate::health::Health;
+use crate::player::Player;
+use crate::save_file::{self, Format};
+use crate::schedule::InGameSet;
+use crate::state::{GameState, OnRunEnd, OnRunStart};
+
+const PROGRESSION_FILE: &str = "progression.ron";
+/// Bump when the save format changes, and teach `Progression::migrate` about the old version.
+const PROGRESSION_VERSION: u32 = 1;
+const CREDITS_PER_ASTEROID: u32 = 5;
+const TEXT_SIZE: f32 = 18.0;
+const HULL_HEALTH_PER_LEVEL: f32 = 20.0;
+const SHIELD_PER_LEVEL: f32 = 10.0;
+const FIRE_RATE_PER_LEVEL: f32 = 0.1;
+const SPEED_PER_LEVEL: f32 = 0.06;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Upgrade {
+    Hull,
+    Shields,
+    FireRate,
+    Speed,
+}
+
+impl Upgrade {
+    pub const ALL: [Upgrade; 4] = [
+        Upgrade::Hull,
+        Upgrade::Shields,
+        Upgrade::Speed,
+        Upgrade::FireRate,
+    ];
+
+    pub fn max_level(&self) -> u8 {
+        5
+    }
+
+    /// Credits needed to buy the level after `level`.
+    pub fn cost(&self, level: u8) -> u32 {
+        let base = match self {
+            Upgrade::Hull | Upgrade::Speed => 50,
+            Upgrade::Shields | Upgrade::FireRate => 80,
+        };
+        base * (level as u32 + 1)
+    }
+
+    /// The upgrade and level that must be bought before this one, making up the upgrade tree.
+    pub fn requires(&self) -> Option<(Upgrade, u8)> {
+        match self {
+            Upgrade::Hull | Upgrade::Speed => None,
+            Upgrade::Shields => Some((Upgrade::Hull, 1)),
+            Upgrade::FireRate => Some((Upgrade::Speed, 1)),
+        }
+    }
+}
+
+/// How the bought upgrades change a ship, applied when it spawns.
+#[derive(Debug, Clone, Copy)]
+pub struct ShipUpgrades {
+    pub health_bonus: f32,
+    pub shield_bonus: f32,
+    pub fire_rate_multiplier: f32,
+    pub speed_multiplier: f32,
+}
+
+/// Everything that carries over between runs, saved to disk whenever it changes.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Progression {
+    pub version: u32,
+    pub credits: u32,
+    #[serde(deserialize_with = "deserialize_upgrades")]
+    pub upgrades: HashMap<Upgrade, u8>,
+}
+
+impl Default for Progression {
+    fn default() -> Self {
+        Self {
+            version: PROGRESSION_VERSION,
+            credits: 0,
+            upgrades: HashMap::default(),
+        }
+    }
+}
+
+impl Progression {
+    pub fn level(&self, upgrade: Upgrade) -> u8 {
+        self.upgrades.get(&upgrade).copied().unwrap_or(0)
+    }
+
+    pub fn can_buy(&self, upgrade: Upgrade) -> bool {
+        let level = self.level(upgrade);
+        level < upgrade.max_level()
+            && self.credits >= upgrade.cost(level)
+            && upgrade
+                .requires()
+                .is_none_or(|(required, required_level)| self.level(required) >= required_level)
+    }
+
+    /// Buys the next level of `upgrade`, returning whether it could be bought.
+    pub fn buy(&mut self, upgrade: Upgrade) -> bool {
+        if !self.can_buy(upgrade) {
+            return false;
+        }
+        let level = self.level(upgrade);
+        self.credits -= upgrade.cost(level);
+        self.upgrades.insert(upgrade, level + 1);
+        true
+    }
+
+    pub fn ship_upgrades(&self) -> ShipUpgrades {
+        let level = |upgrade| self.level(upgrade) as f32;
+        ShipUpgrades {
+            health_bonus: level(Upgrade::Hull) * HULL_HEALTH_PER_LEVEL,
+            shield_bonus: level(Upgrade::Shields) * SHIELD_PER_LEVEL,
+            fire_rate_multiplier: 1.0 + level(Upgrade::FireRate) * FIRE_RATE_PER_LEVEL,
+            speed_multiplier: 1.0 + level(Upgrade::Speed) * SPEED_PER_LEVEL,
+        }
+    }
+
+    /// Loads the saved progression, starting afresh if there is none. A save that can't be read,
+    /// or one from a newer version of the game, is backed up before it can be written over.
+    pub fn load() -> Self {
+        let Some(path) = progression_path() else {
+            return Self::default();
+        };
+        let Some(progression) = save_file::load(&path, |contents| ron::from_str::<Self>(contents))
+        else {
+            return Self::default();
+        };
+        if progression.version > PROGRESSION_VERSION {
+            warn!(
+                "progression was saved by a newer version ({}), loading what we can",
+                progression.version
+            );
+            save_file::back_up(&path);
+        }
+        progression.migrate()
+    }
+
+    /// Brings a save from an older version of the game up to date. Fields added since are
+    /// filled in by `serde(default)`; anything that needs more than that goes here.
+    fn migrate(mut self) -> Self {
+        // levels above a lowered cap are refunded rather than lost
+        for (upgrade, level) in self.upgrades.iter_mut() {
+            while *level > upgrade.max_level() {
+                *level -= 1;
+                self.credits += upgrade.cost(*level);
+            }
+        }
+        self.version = PROGRESSION_VERSION;
+        self
+    }
+
+    pub fn save(&self) {
+        let Some(path) = progression_path() else {
+            return;
+        };
+        save_file::save(self, &path, Format::Ron);
+    }
+}
+
+/// Reads the upgrade levels, skipping any upgrade this version of the game doesn't have, so a
+/// removed or renamed upgrade doesn't make the whole save unreadable.
+fn deserialize_upgrades<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<Upgrade, u8>, D::Error> {
+    let levels = HashMap::<UpgradeName, u8>::deserialize(deserializer)?;
+    Ok(levels
+        .into_iter()
+        .filter_map(|(UpgradeName(name), level)| {
+            match Upgrade::deserialize(StrDeserializer::<ValueError>::new(&name)) {
+                Ok(upgrade) => Some((upgrade, level)),
+                Err(_) => {
+                    warn!("ignoring unknown upgrade {} in progression", name);
+                    None
+                }
+            }
+        })
+        .collect())
+}
+
+/// The name of an upgrade as saved, whether or not it's still an `Upgrade`.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct UpgradeName(String);
+
+impl<'de> Deserialize<'de> for UpgradeName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NameVisitor;
+
+        impl<'de> Visitor<'de> for NameVisitor {
+            type Value = UpgradeName;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an upgrade name")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(UpgradeName(value.to_string()))
+            }
+        }
+
+        deserializer.deserialize_identifier(NameVisitor)
+    }
+}
+
+fn progression_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(PROGRESSION_FILE))
+}
+
+/// Credits earned so far this run, banked into `Progression` when the run ends.
+#[derive(Resource, Debug, Default)]
+pub struct RunCredits {
+    pub value: u32,
+    banked: bool,
+}
+
+/// The upgrade highlighted in the shop.
+#[derive(Resource, Debug, Default)]
+struct UpgradeShop {
+    cursor: usize,
+}
+
+#[derive(Component, Debug)]
+struct UpgradeShopText;
+
+pub struct ProgressionPlugin;
+
+impl Plugin for ProgressionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Progression::load())
+            .init_resource::<RunCredits>()
+            .init_resource::<UpgradeShop>()
+            .add_systems(OnEnter(GameState::ShipSelection), spawn_upgrade_shop)
+            .add_systems(
+                Update,
+                buy_upgrades.run_if(in_state(GameState::ShipSelection)),
+            )
+            .add_systems(OnExit(GameState::ShipSelection), despawn_upgrade_shop)
+            .add_systems(OnRunStart, start_run)
+            .add_systems(Update, earn_credits.in_set(InGameSet::DespawnEntities))
+            .add_systems(OnEnter(GameState::GameOver), bank_credits)
+            .add_systems(OnRunEnd, bank_credits)
+            .add_systems(Update, save_progression);
+    }
+}
+
+fn spawn_upgrade_shop(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: TEXT_SIZE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(20.0),
+            left: Val::Px(20.0),
+            ..default()
+        }),
+        UpgradeShopText,
+    ));
+}
+
+fn despawn_upgrade_shop(mut commands: Commands, query: Query<Entity, With<UpgradeShopText>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// The first player browses the shop with the menu actions and buys with boost.
+fn buy_upgrades(
+    mut progression: ResMut<Progression>,
+    mut shop: ResMut<UpgradeShop>,
+    mut text_query: Query<&mut Text, With<UpgradeShopText>>,
+    action_state: Res<ActionState>,
+) {
+    let player = Player(0);
+    let upgrade_count = Upgrade::ALL.len();
+    if action_state.just_pressed(player, Action::MenuUp) {
+        shop.cursor = (shop.cursor + upgrade_count - 1) % upgrade_count;
+    }
+    if action_state.just_pressed(player, Action::MenuDown) {
+        shop.cursor = (shop.cursor + 1) % upgrade_count;
+    }
+    if action_state.just_pressed(player, Action::Boost) {
+        let upgrade = Upgrade::ALL[shop.cursor];
+        if progression.buy(upgrade) {
+            info!("bought {:?} level {}", upgrade, progression.level(upgrade));
+        }
+    }
+
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let mut lines = vec![format!("Upgrades - {} credits", progression.credits)];
+    for (index, &upgrade) in Upgrade::ALL.iter().enumerate() {
+        let level = progression.level(upgrade);
+        let status = if level >= upgrade.max_level() {
+            "max".to_string()
+        } else {
+            match upgrade.requires() {
+                Some((required, required_level))
+                    if progression.level(required) < required_level =>
+                {
+                    format!("needs {:?} {}", required, required_level)
+                }
+                _ => format!("{} credits", upgrade.cost(level)),
+            }
+        };
+        lines.push(format!(
+            "{} {:?} {}/{} ({})",
+            if index == shop.cursor { ">" } else { " " },
+            upgrade,
+            level,
+            upgrade.max_level(),
+            status,
+        ));
+    }
+    lines.push("Up and down to browse, boost to buy".to_string());
+    text.sections[0].value = lines.join("\n");
+}
+
+fn start_run(mut run_credits: ResMut<RunCredits>) {
+    *run_credits = RunCredits::default();
+}
+
+fn earn_credits(mut run_credits: ResMut<RunCredits>, query: Query<&Health, With<Asteroid>>) {
+    // despawn_dead_entities removes them at the end of this set, so each one counts once, and
+    // only those a player destroyed earn anything
+    let destroyed = query
+        .iter()
+        .filter(|health| health.value <= 0.0 && health.last_hit_by.is_some())
+        .count() as u32;
+    run_credits.value += destroyed * CREDITS_PER_ASTEROID;
+}
+
+/// Banks at game over, so nothing is lost if the game is closed on the game over screen, and
+/// whenever a run is left, so restarting or quitting from the pause menu keeps them too. Kept in
+/// `RunCredits` as well until the next run starts, for the game over screen.
+fn bank_credits(mut progression: ResMut<Progression>, mut run_credits: ResMut<RunCredits>) {
+    if run_credits.banked {
+        return;
+    }
+    run_credits.banked = true;
+    info!("banked {} credits", run_credits.value);
+    progression.credits += run_credits.value;
+}
+
+fn save_progression(progression: Res<Progression>) {
+    if progression.is_changed() && !progression.is_added() {
+        progression.save();
+    }
+}
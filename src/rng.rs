@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::state::OnRunStart;
+
+/// Where what turns up in the arena gets its randomness from. Reseeded for every run, so the
+/// seed can be kept with the run's results.
+#[derive(Resource, Debug, Deref, DerefMut)]
+pub struct GameRng {
+    seed: u64,
+    #[deref]
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GameRng::new(rand::thread_rng().gen()))
+            .add_systems(OnRunStart, reseed);
+    }
+}
+
+fn reseed(mut rng: ResMut<GameRng>) {
+    *rng = GameRng::new(rand::thread_rng().gen());
+}
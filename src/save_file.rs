@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use serde::Serialize;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How a file written by `save` is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Ron,
+    Json,
+}
+
+/// Reads the file at `path` with `parse`, or `None` if there isn't one. A file that can't be
+/// parsed is moved aside with `back_up` first, so saving a fresh one in its place loses nothing.
+pub fn load<T, E: Display>(path: &Path, parse: impl FnOnce(&str) -> Result<T, E>) -> Option<T> {
+    let contents = fs::read_to_string(path).ok()?;
+    match parse(&contents) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            warn!("ignoring invalid {:?}: {}", path, error);
+            back_up(path);
+            None
+        }
+    }
+}
+
+/// Moves the file at `path` aside, stamped with the time so an earlier backup is never replaced.
+pub fn back_up(path: &Path) {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".{}.bak", seconds));
+    match fs::rename(path, &backup) {
+        Ok(()) => warn!("kept a copy of {:?} as {:?}", path, backup),
+        Err(error) => warn!("failed to back up {:?}: {}", path, error),
+    }
+}
+
+/// Writes `value` to `path`, creating its directory if needed. The game carries on whether or
+/// not it could be saved, so failing only logs a warning.
+pub fn save<T: Serialize>(value: &T, path: &Path, format: Format) {
+    let contents = match format {
+        Format::Ron => {
+            ron::ser::to_string_pretty(value, default()).map_err(|error| error.to_string())
+        }
+        Format::Json => serde_json::to_string_pretty(value).map_err(|error| error.to_string()),
+    };
+    let result = contents.and_then(|contents| {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+        }
+        fs::write(path, contents).map_err(|error| error.to_string())
+    });
+    if let Err(error) = result {
+        warn!("failed to save {:?}: {}", path, error);
+    }
+}
@@ -0,0 +1,245 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::collision::DamageTaken;
+use crate::despawn::DespawnOnRunEnd;
+use crate::health::Health;
+use crate::player::Player;
+use crate::schedule::InGameSet;
+use crate::spaceship::Spaceship;
+use crate::state::OnRunStart;
+use crate::wave::WaveStarted;
+
+/// Each kill within this long of the last one grows the combo; after that it shrinks a step at
+/// a time, one step per this long.
+const COMBO_SECONDS: f32 = 2.5;
+const COMBO_STEP: f32 = 0.25;
+const MAX_COMBO: u32 = 12;
+/// Per wave number, for each player who gets through a wave without taking a hit.
+const NO_DAMAGE_BONUS_PER_WAVE: u32 = 250;
+const TEXT_SIZE: f32 = 20.0;
+
+/// What destroying this is worth, before the combo multiplier.
+#[derive(Component, Debug)]
+pub struct Points {
+    pub value: u32,
+}
+
+impl Points {
+    pub fn new(value: u32) -> Self {
+        Self { value }
+    }
+}
+
+#[derive(Debug)]
+pub struct PlayerScore {
+    pub value: u32,
+    /// Kills in quick succession, raising the multiplier.
+    pub combo: u32,
+    combo_timer: Timer,
+    /// Whether the player has been hit since the current wave started.
+    damaged: bool,
+}
+
+impl Default for PlayerScore {
+    fn default() -> Self {
+        Self {
+            value: 0,
+            combo: 0,
+            combo_timer: Timer::from_seconds(COMBO_SECONDS, TimerMode::Once),
+            damaged: false,
+        }
+    }
+}
+
+impl PlayerScore {
+    pub fn multiplier(&self) -> f32 {
+        1.0 + self.combo as f32 * COMBO_STEP
+    }
+}
+
+/// Each player's score for the current run.
+#[derive(Resource, Debug, Default)]
+pub struct Score {
+    pub players: HashMap<Player, PlayerScore>,
+}
+
+impl Score {
+    pub fn total(&self) -> u32 {
+        self.players.values().map(|score| score.value).sum()
+    }
+
+    /// Adds `points` to the player's score, returning their new score.
+    fn award(&mut self, player: Player, points: u32) -> u32 {
+        let score = self.players.entry(player).or_default();
+        score.value += points;
+        score.value
+    }
+}
+
+#[derive(Event, Debug)]
+pub struct ScoreChanged {
+    pub player: Player,
+    pub points: u32,
+    pub score: u32,
+}
+
+impl ScoreChanged {
+    pub fn new(player: Player, points: u32, score: u32) -> Self {
+        Self {
+            player,
+            points,
+            score,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct ScoreText;
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .add_systems(OnRunStart, (reset_score, spawn_score_text))
+            .add_systems(
+                Update,
+                (track_damage, score_kills).in_set(InGameSet::DespawnEntities),
+            )
+            .add_systems(
+                Update,
+                (decay_combos, award_no_damage_bonuses, update_score_text)
+                    .chain()
+                    .in_set(InGameSet::EntityUpdates),
+            )
+            .add_event::<ScoreChanged>();
+    }
+}
+
+fn reset_score(mut score: ResMut<Score>) {
+    *score = Score::default();
+}
+
+fn spawn_score_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: TEXT_SIZE,
+                ..default()
+            },
+        )
+        .with_text_justify(JustifyText::Right)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(16.0),
+            right: Val::Px(20.0),
+            ..default()
+        }),
+        ScoreText,
+        DespawnOnRunEnd,
+    ));
+}
+
+fn track_damage(
+    mut damage_taken_event_reader: EventReader<DamageTaken>,
+    mut score: ResMut<Score>,
+    player_query: Query<&Player>,
+) {
+    for &DamageTaken { entity, .. } in damage_taken_event_reader.read() {
+        if let Ok(&player) = player_query.get(entity) {
+            score.players.entry(player).or_default().damaged = true;
+        }
+    }
+}
+
+/// Credits each kill to the player who landed the final hit, scaled by their combo, which the
+/// kill then grows.
+fn score_kills(
+    mut score_changed_event_writer: EventWriter<ScoreChanged>,
+    mut score: ResMut<Score>,
+    query: Query<(&Health, &Points)>,
+) {
+    // despawn_dead_entities removes them at the end of this set, so each one counts once
+    for (health, points) in query.iter() {
+        if health.value > 0.0 {
+            continue;
+        }
+        let Some(player) = health.last_hit_by else {
+            continue;
+        };
+
+        let player_score = score.players.entry(player).or_default();
+        let awarded = (points.value as f32 * player_score.multiplier()).round() as u32;
+        player_score.combo = (player_score.combo + 1).min(MAX_COMBO);
+        player_score.combo_timer.reset();
+        let total = score.award(player, awarded);
+        score_changed_event_writer.send(ScoreChanged::new(player, awarded, total));
+    }
+}
+
+fn decay_combos(mut score: ResMut<Score>, time: Res<Time>) {
+    for player_score in score.players.values_mut() {
+        if player_score.combo == 0 {
+            continue;
+        }
+        if player_score.combo_timer.tick(time.delta()).finished() {
+            player_score.combo -= 1;
+            player_score.combo_timer.reset();
+        }
+    }
+}
+
+/// Each wave starting means the last one was cleared, so anyone still flying who came through
+/// it untouched gets a bonus.
+fn award_no_damage_bonuses(
+    mut wave_started_event_reader: EventReader<WaveStarted>,
+    mut score_changed_event_writer: EventWriter<ScoreChanged>,
+    mut score: ResMut<Score>,
+    ship_query: Query<&Player, With<Spaceship>>,
+) {
+    for &WaveStarted { number } in wave_started_event_reader.read() {
+        let cleared = number - 1;
+        if cleared > 0 {
+            let bonus = cleared * NO_DAMAGE_BONUS_PER_WAVE;
+            for &player in ship_query.iter() {
+                if score
+                    .players
+                    .get(&player)
+                    .is_some_and(|score| score.damaged)
+                {
+                    continue;
+                }
+                let total = score.award(player, bonus);
+                score_changed_event_writer.send(ScoreChanged::new(player, bonus, total));
+            }
+        }
+        for player_score in score.players.values_mut() {
+            player_score.damaged = false;
+        }
+    }
+}
+
+fn update_score_text(score: Res<Score>, mut query: Query<&mut Text, With<ScoreText>>) {
+    if !score.is_changed() {
+        return;
+    }
+    let Ok(mut text) = query.get_single_mut() else {
+        return;
+    };
+    let mut players: Vec<_> = score.players.iter().collect();
+    players.sort_by_key(|(player, _)| player.0);
+    let lines: Vec<String> = players
+        .into_iter()
+        .map(|(player, player_score)| {
+            format!(
+                "P{} {}  x{:.2}",
+                player.0 + 1,
+                player_score.value,
+                player_score.multiplier()
+            )
+        })
+        .collect();
+    text.sections[0].value = lines.join("\n");
+}
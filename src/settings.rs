@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::player::{MatchMode, Player, MAX_LOCAL_PLAYERS};
+use crate::save_file::{self, Format};
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlScheme {
+    /// Turn with the turn actions or the gamepad's left stick.
+    #[default]
+    Keyboard,
+    /// Turn towards the mouse cursor. Only applies to the first player.
+    MouseAim,
+}
+
+impl ControlScheme {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ControlScheme::Keyboard => "Keyboard",
+            ControlScheme::MouseAim => "Mouse aim",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ControlScheme::Keyboard => ControlScheme::MouseAim,
+            ControlScheme::MouseAim => ControlScheme::Keyboard,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub control_scheme: ControlScheme,
+    pub local_players: u8,
+    pub match_mode: MatchMode,
+    /// Shown in the high score table, in player order. Players without one go by their number.
+    pub player_names: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            control_scheme: ControlScheme::default(),
+            local_players: 1,
+            match_mode: MatchMode::default(),
+            player_names: vec![],
+        }
+    }
+}
+
+impl Settings {
+    pub fn player_count(&self) -> u8 {
+        self.local_players.clamp(1, MAX_LOCAL_PLAYERS)
+    }
+
+    /// One more player, going back to one after the most that can play.
+    pub fn next_player_count(&self) -> u8 {
+        self.player_count() % MAX_LOCAL_PLAYERS + 1
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = Player> + Clone {
+        (0..self.player_count()).map(Player)
+    }
+
+    pub fn player_name(&self, player: Player) -> String {
+        self.player_names
+            .get(player.0 as usize)
+            .cloned()
+            .unwrap_or_else(|| format!("Player {}", player.0 + 1))
+    }
+
+    /// Loads the player's saved settings, or the defaults if there are none or they can't be read.
+    pub fn load() -> Self {
+        settings_path()
+            .and_then(|path| save_file::load(&path, |contents| ron::from_str(contents)))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let Some(path) = settings_path() else {
+            return;
+        };
+        save_file::save(self, &path, Format::Ron);
+    }
+}
+
+fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(SETTINGS_FILE))
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        // settings inserted up front, e.g. by tests, are used instead of the saved ones
+        if !app.world.contains_resource::<Settings>() {
+            app.insert_resource(Settings::load());
+        }
+        app.add_systems(Update, save_settings);
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    if settings.is_changed() && !settings.is_added() {
+        settings.save();
+    }
+}
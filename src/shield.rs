@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+
+use crate::schedule::InGameSet;
+
+#[derive(Component, Debug)]
+pub struct Shield {
+    pub current: f32,
+    pub max: f32,
+    pub regen_rate: f32,
+    pub regen_delay: Timer,
+}
+
+impl Shield {
+    pub fn new(max: f32, regen_rate: f32, regen_delay_seconds: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            regen_rate,
+            regen_delay: Timer::from_seconds(regen_delay_seconds, TimerMode::Once),
+        }
+    }
+
+    /// Soaks up as much of `amount` as the shield can hold and returns what is left over for
+    /// the hull. Any hit, even a fully absorbed one, restarts the regen delay.
+    pub fn absorb(&mut self, amount: f32) -> f32 {
+        self.regen_delay.reset();
+        let absorbed = amount.min(self.current);
+        self.current -= absorbed;
+        amount - absorbed
+    }
+
+    pub fn is_depleted(&self) -> bool {
+        self.current <= 0.0
+    }
+}
+
+#[derive(Event, Debug)]
+pub struct ShieldDepleted {
+    pub entity: Entity,
+}
+
+impl ShieldDepleted {
+    pub fn new(entity: Entity) -> Self {
+        Self { entity }
+    }
+}
+
+/// Sent when a depleted shield starts holding charge again.
+#[derive(Event, Debug)]
+pub struct ShieldRestored {
+    pub entity: Entity,
+}
+
+impl ShieldRestored {
+    pub fn new(entity: Entity) -> Self {
+        Self { entity }
+    }
+}
+
+pub struct ShieldPlugin;
+
+impl Plugin for ShieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, regenerate_shields.in_set(InGameSet::EntityUpdates))
+            .add_event::<ShieldDepleted>()
+            .add_event::<ShieldRestored>();
+    }
+}
+
+fn regenerate_shields(
+    mut shield_restored_event_writer: EventWriter<ShieldRestored>,
+    mut query: Query<(Entity, &mut Shield)>,
+    time: Res<Time>,
+) {
+    for (entity, mut shield) in query.iter_mut() {
+        if !shield.regen_delay.tick(time.delta()).finished() || shield.current >= shield.max {
+            continue;
+        }
+
+        let was_depleted = shield.is_depleted();
+        shield.current =
+            (shield.current + shield.regen_rate * time.delta_seconds()).min(shield.max);
+        if was_depleted && !shield.is_depleted() {
+            shield_restored_event_writer.send(ShieldRestored::new(entity));
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext, LoadedFolder};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::weapon::Weapon;
+
+const SHIP_DEFINITIONS_FOLDER: &str = "ships";
+
+/// A class of ship the player can fly, loaded from a `.ship.ron` file.
+#[derive(Asset, TypePath, Debug)]
+pub struct ShipDefinition {
+    pub name: String,
+    pub model: Handle<Scene>,
+    pub collider_radius: f32,
+    pub movement_speed: f32,
+    pub rotation_speed: f32,
+    pub health: f32,
+    pub weapon: WeaponDefinition,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeaponDefinition {
+    pub seconds_between_shots: f32,
+    #[serde(default)]
+    pub heat_per_shot: f32,
+    #[serde(default)]
+    pub energy_per_shot: f32,
+    /// `None` for unlimited ammo.
+    #[serde(default)]
+    pub ammo: Option<u32>,
+    /// Makes projectiles home in on the nearest opponent, steering this hard.
+    #[serde(default)]
+    pub homing_acceleration: Option<f32>,
+}
+
+impl WeaponDefinition {
+    pub fn weapon(&self) -> Weapon {
+        Weapon {
+            ammo: self.ammo,
+            homing_acceleration: self.homing_acceleration,
+            ..Weapon::new(self.seconds_between_shots)
+                .with_heat_per_shot(self.heat_per_shot)
+                .with_energy_per_shot(self.energy_per_shot)
+        }
+    }
+}
+
+/// The on-disk form of a `ShipDefinition`, with the model as an asset path.
+#[derive(Debug, Deserialize)]
+struct ShipDefinitionFile {
+    name: String,
+    model: String,
+    collider_radius: f32,
+    movement_speed: f32,
+    rotation_speed: f32,
+    health: f32,
+    weapon: WeaponDefinition,
+}
+
+#[derive(Debug, Error)]
+pub enum ShipDefinitionLoaderError {
+    #[error("could not read ship definition: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse ship definition: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+#[derive(Default)]
+struct ShipDefinitionLoader;
+
+impl AssetLoader for ShipDefinitionLoader {
+    type Asset = ShipDefinition;
+    type Settings = ();
+    type Error = ShipDefinitionLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let file: ShipDefinitionFile = ron::de::from_bytes(&bytes)?;
+            Ok(ShipDefinition {
+                name: file.name,
+                model: load_context.load(file.model),
+                collider_radius: file.collider_radius,
+                movement_speed: file.movement_speed,
+                rotation_speed: file.rotation_speed,
+                health: file.health,
+                weapon: file.weapon,
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ship.ron"]
+    }
+}
+
+/// Every ship definition in the ships folder, loaded at startup.
+#[derive(Resource, Debug, Default)]
+pub struct ShipClasses {
+    pub folder: Handle<LoadedFolder>,
+}
+
+/// Looks up the loaded ship classes.
+#[derive(SystemParam)]
+pub struct ShipCatalog<'w> {
+    ship_classes: Res<'w, ShipClasses>,
+    folders: Res<'w, Assets<LoadedFolder>>,
+    definitions: Res<'w, Assets<ShipDefinition>>,
+}
+
+impl ShipCatalog<'_> {
+    /// The loaded ship classes sorted by name, or `None` while the folder is still loading.
+    pub fn classes(&self) -> Option<Vec<(Handle<ShipDefinition>, &ShipDefinition)>> {
+        let folder = self.folders.get(&self.ship_classes.folder)?;
+        let mut classes: Vec<_> = folder
+            .handles
+            .iter()
+            .filter_map(|handle| handle.clone().try_typed::<ShipDefinition>().ok())
+            .filter_map(|handle| {
+                let definition = self.definitions.get(&handle)?;
+                Some((handle, definition))
+            })
+            .collect();
+        classes.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name));
+        Some(classes)
+    }
+}
+
+pub struct ShipDefinitionPlugin;
+
+impl Plugin for ShipDefinitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<ShipDefinition>()
+            .init_asset_loader::<ShipDefinitionLoader>()
+            .init_resource::<ShipClasses>()
+            .add_systems(PreStartup, load_ship_classes);
+    }
+}
+
+fn load_ship_classes(mut ship_classes: ResMut<ShipClasses>, asset_server: Res<AssetServer>) {
+    ship_classes.folder = asset_server.load_folder(SHIP_DEFINITIONS_FOLDER);
+}
@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+
+use crate::action::{Action, ActionState};
+use crate::player::Player;
+use crate::settings::Settings;
+use crate::ship_definition::{ShipCatalog, ShipDefinition};
+use crate::state::GameState;
+
+const TEXT_SIZE: f32 = 24.0;
+
+/// The ship class each player picked, used whenever their ship is spawned.
+#[derive(Resource, Debug, Default)]
+pub struct SelectedShips {
+    pub ships: HashMap<Player, Handle<ShipDefinition>>,
+}
+
+/// Each player's highlighted class, and which players have locked theirs in.
+#[derive(Resource, Debug, Default)]
+struct ShipSelection {
+    cursors: HashMap<Player, usize>,
+    confirmed: HashSet<Player>,
+}
+
+#[derive(Component, Debug)]
+struct ShipSelectionText;
+
+pub struct ShipSelectionPlugin;
+
+impl Plugin for ShipSelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectedShips>()
+            .init_resource::<ShipSelection>()
+            .add_systems(OnEnter(GameState::ShipSelection), spawn_selection_text)
+            .add_systems(
+                Update,
+                select_ships.run_if(in_state(GameState::ShipSelection)),
+            )
+            .add_systems(OnExit(GameState::ShipSelection), despawn_selection_text);
+    }
+}
+
+fn spawn_selection_text(mut commands: Commands, mut selection: ResMut<ShipSelection>) {
+    *selection = ShipSelection::default();
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: TEXT_SIZE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(30.0),
+            left: Val::Percent(30.0),
+            ..default()
+        }),
+        ShipSelectionText,
+    ));
+}
+
+fn despawn_selection_text(mut commands: Commands, query: Query<Entity, With<ShipSelectionText>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Players browse with their turn actions and lock in with fire. Play starts once every local
+/// player has chosen.
+fn select_ships(
+    mut next_state: ResMut<NextState<GameState>>,
+    mut selection: ResMut<ShipSelection>,
+    mut selected_ships: ResMut<SelectedShips>,
+    mut text_query: Query<&mut Text, With<ShipSelectionText>>,
+    action_state: Res<ActionState>,
+    settings: Res<Settings>,
+    catalog: ShipCatalog,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let Some(classes) = catalog.classes() else {
+        text.sections[0].value = "Loading ships...".to_string();
+        return;
+    };
+    if classes.is_empty() {
+        text.sections[0].value = "No ships found".to_string();
+        return;
+    }
+
+    let players: Vec<Player> = settings.players().collect();
+    let mut lines = vec!["Choose your ship".to_string(), String::new()];
+    for &player in players.iter() {
+        let confirmed = selection.confirmed.contains(&player);
+        let cursor = selection.cursors.entry(player).or_default();
+        if !confirmed {
+            if action_state.just_pressed(player, Action::TurnLeft) {
+                *cursor = (*cursor + classes.len() - 1) % classes.len();
+            }
+            if action_state.just_pressed(player, Action::TurnRight) {
+                *cursor = (*cursor + 1) % classes.len();
+            }
+        }
+        *cursor = (*cursor).min(classes.len() - 1);
+        let (handle, definition) = &classes[*cursor];
+
+        if !confirmed && action_state.just_pressed(player, Action::Fire) {
+            selection.confirmed.insert(player);
+            selected_ships.ships.insert(player, handle.clone());
+        }
+
+        lines.push(format!(
+            "Player {}: < {} >  speed {}, turn {}, health {}{}",
+            player.0 + 1,
+            definition.name,
+            definition.movement_speed,
+            definition.rotation_speed,
+            definition.health,
+            if selection.confirmed.contains(&player) {
+                "  [ready]"
+            } else {
+                ""
+            },
+        ));
+    }
+    lines.push(String::new());
+    lines.push("Turn to browse, fire to confirm".to_string());
+    text.sections[0].value = lines.join("\n");
+
+    if players
+        .iter()
+        .all(|player| selection.confirmed.contains(player))
+    {
+        next_state.set(GameState::InGame);
+    }
+}
@@ -1,131 +1,528 @@
-use crate::asset_loader::SceneAssets;
+use crate::ability::{Ability, Boost, Dash, Roll, Strafe, UseAbilityEvent};
+use crate::camera::cursor_to_gameplay_plane;
 use crate::collision::{Collider, CollisionDamage};
-use crate::despawn::DespawnWhenRemote;
-use crate::health::Health;
-use crate::movement::{Acceleration, MovingObjectBundle, Velocity};
+use crate::controls::{ControlSourceSet, GamepadControlled, KeyboardControlled, ShipControls};
+use crate::despawn::DespawnOnRunEnd;
+use crate::faction::Faction;
+use crate::health::{Health, Invulnerable};
+use crate::movement::{planar_right, Acceleration, MovingObjectBundle, Velocity};
+use crate::player::{MatchMode, Player, MAX_LOCAL_PLAYERS};
+use crate::powerup::SpeedBoost;
+use crate::progression::{Progression, ShipUpgrades};
 use crate::schedule::InGameSet;
+use crate::settings::{ControlScheme, Settings};
+use crate::shield::Shield;
+use crate::ship_definition::{ShipDefinition, WeaponDefinition};
+use crate::ship_selection::SelectedShips;
+use crate::state::OnRunStart;
+use crate::weapon::{Energy, FireWeaponEvent, Heat};
+use bevy::ecs::system::{EntityCommands, SystemParam};
 use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy::window::PrimaryWindow;
 
-const SPACESHIP_RADIUS: f32 = 5.;
-const MISSILE_RADIUS: f32 = 1.;
 const STARTING_TRANSLATION: Vec3 = Vec3::new(0.0, 0.0, -20.0);
-const MOVEMENT_SPEED: f32 = 25.0;
-const ROTATION_SPEED: f32 = 2.5;
+const PLAYER_SPACING: f32 = 20.0;
+const PLAYER_MARKER_RADIUS: f32 = 4.0;
+const PLAYER_MARKER_THICKNESS: f32 = 0.4;
+const PLAYER_MARKER_OFFSET: f32 = -1.5;
 const ROLL_SPEED: f32 = 3.0;
-const MISSILE_SPEED: f32 = 55.0;
-const MISSILE_FORWARD_SPAWN: f32 = 6.5;
-const SPACESHIP_HEALTH: f32 = 100.0;
+const BARREL_ROLL_WINDOW_SECONDS: f32 = 2.5;
+const BARREL_ROLL_COOLDOWN_SECONDS: f32 = 3.0;
+const BARREL_ROLL_EVASION_SECONDS: f32 = 0.75;
+const BARREL_ROLL_DISPLACEMENT: f32 = 6.0;
+const STRAFE_SPEED: f32 = 18.0;
+const BOOST_MULTIPLIER: f32 = 1.8;
+const BOOST_ENERGY_COST: f32 = 25.0;
+const BOOST_SECONDS: f32 = 1.5;
+const BOOST_COOLDOWN_SECONDS: f32 = 4.0;
+const DASH_DISTANCE: f32 = 12.0;
+const DASH_ENERGY_COST: f32 = 15.0;
+const DASH_INVULNERABLE_SECONDS: f32 = 0.3;
+const DASH_COOLDOWN_SECONDS: f32 = 1.0;
 const SPACESHIP_COLLISION_DAMAGE: f32 = 100.0;
-const MISSILE_HEALTH: f32 = 1.0;
-const MISSILE_COLLISION_DAMAGE: f32 = 5.0;
+const SPACESHIP_SHIELD: f32 = 50.0;
+const SPACESHIP_SHIELD_REGEN: f32 = 10.0;
+const SPACESHIP_SHIELD_REGEN_DELAY: f32 = 3.0;
+const SPACESHIP_ENERGY: f32 = 100.0;
+const SPACESHIP_ENERGY_REGEN: f32 = 15.0;
+const WEAPON_MAX_HEAT: f32 = 100.0;
+const WEAPON_COOLING_RATE: f32 = 30.0;
+const WEAPON_OVERHEAT_SECONDS: f32 = 2.0;
+const STARTING_LIVES: u32 = 3;
+const RESPAWN_DELAY_SECONDS: f32 = 2.0;
+const RESPAWN_INVULNERABLE_SECONDS: f32 = 3.0;
+const RESPAWN_SAFE_DISTANCE: f32 = 15.0;
+const RESPAWN_SEARCH_RINGS: [f32; 2] = [10.0, 20.0];
+const RESPAWN_SEARCH_DIRECTIONS: usize = 8;
 
 #[derive(Component, Debug)]
 pub struct Spaceship;
 
+/// Handling figures from the ship's `ShipDefinition`.
 #[derive(Component, Debug)]
-pub struct SpaceshipMissile;
+pub struct ShipStats {
+    pub movement_speed: f32,
+    pub rotation_speed: f32,
+}
+
+impl ShipStats {
+    pub fn new(movement_speed: f32, rotation_speed: f32) -> Self {
+        Self {
+            movement_speed,
+            rotation_speed,
+        }
+    }
+}
+
+/// Lives left for each player, counting the ship they are flying.
+#[derive(Resource, Debug, Default)]
+pub struct Lives {
+    pub remaining: HashMap<Player, u32>,
+}
+
+/// Players waiting to respawn, and how long until they do.
+#[derive(Resource, Debug, Default)]
+pub struct RespawnTimers {
+    pub pending: HashMap<Player, Timer>,
+}
+
+/// Sent when the last life that matters is lost: everyone's in co-op, or all but one player's
+/// in versus.
+#[derive(Event, Debug, Default)]
+pub struct RunOver;
+
+/// The ring under each ship that shows whose it is.
+#[derive(Resource, Debug)]
+struct PlayerMarkers {
+    mesh: Handle<Mesh>,
+    materials: HashMap<Player, Handle<StandardMaterial>>,
+}
 
 pub struct SpaceshipPlugin;
 
 impl Plugin for SpaceshipPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_spaceship).add_systems(
-            Update,
-            (spaceship_movement_controls, spaceship_weapon_controls)
-                .chain()
-                .in_set(InGameSet::UserInput),
-        );
+        app.init_resource::<Lives>()
+            .init_resource::<RespawnTimers>()
+            .add_systems(Startup, create_player_markers)
+            .add_systems(OnRunStart, spawn_spaceships)
+            .add_systems(
+                Update,
+                (
+                    mouse_aim_controls.run_if(control_scheme_is(ControlScheme::MouseAim)),
+                    ship_movement_controls,
+                    ship_weapon_controls,
+                    ship_ability_controls,
+                )
+                    .chain()
+                    .after(ControlSourceSet)
+                    .in_set(InGameSet::UserInput),
+            )
+            .add_systems(Update, respawn_spaceship.in_set(InGameSet::EntityUpdates))
+            .add_systems(
+                Update,
+                spaceship_destroyed.in_set(InGameSet::DespawnEntities),
+            )
+            .add_event::<RunOver>();
     }
 }
 
-fn spawn_spaceship(mut commands: Commands, scene_assets: Res<SceneAssets>) {
-    commands.spawn((
+fn spaceship_bundle(
+    definition: &ShipDefinition,
+    upgrades: ShipUpgrades,
+    translation: Vec3,
+) -> impl Bundle {
+    let weapon = WeaponDefinition {
+        seconds_between_shots: definition.weapon.seconds_between_shots
+            / upgrades.fire_rate_multiplier,
+        ..definition.weapon.clone()
+    };
+    (
         MovingObjectBundle {
             acceleration: Acceleration::new(Vec3::ZERO),
-            collider: Collider::new(SPACESHIP_RADIUS),
+            collider: Collider::new(definition.collider_radius),
             velocity: Velocity::new(Vec3::ZERO),
             model: SceneBundle {
-                scene: scene_assets.spaceship.clone(),
-                transform: Transform::from_translation(STARTING_TRANSLATION),
+                scene: definition.model.clone(),
+                transform: Transform::from_translation(translation),
                 ..default()
             },
         },
         Spaceship,
-        Health::new(SPACESHIP_HEALTH),
+        ShipStats::new(
+            definition.movement_speed * upgrades.speed_multiplier,
+            definition.rotation_speed,
+        ),
+        (
+            ShipControls::default(),
+            KeyboardControlled,
+            GamepadControlled,
+        ),
+        Health::new(definition.health + upgrades.health_bonus),
         CollisionDamage::new(SPACESHIP_COLLISION_DAMAGE),
-    ));
+        Shield::new(
+            SPACESHIP_SHIELD + upgrades.shield_bonus,
+            SPACESHIP_SHIELD_REGEN,
+            SPACESHIP_SHIELD_REGEN_DELAY,
+        ),
+        weapon.weapon(),
+        Heat::new(
+            WEAPON_MAX_HEAT,
+            WEAPON_COOLING_RATE,
+            WEAPON_OVERHEAT_SECONDS,
+        ),
+        Energy::new(SPACESHIP_ENERGY, SPACESHIP_ENERGY_REGEN),
+        Strafe::new(STRAFE_SPEED),
+        Boost::new(
+            BOOST_MULTIPLIER,
+            BOOST_ENERGY_COST,
+            BOOST_SECONDS,
+            BOOST_COOLDOWN_SECONDS,
+        ),
+        Dash::new(
+            DASH_DISTANCE,
+            DASH_ENERGY_COST,
+            DASH_INVULNERABLE_SECONDS,
+            DASH_COOLDOWN_SECONDS,
+        ),
+        Roll::new(
+            ROLL_SPEED,
+            BARREL_ROLL_WINDOW_SECONDS,
+            BARREL_ROLL_COOLDOWN_SECONDS,
+            BARREL_ROLL_EVASION_SECONDS,
+            BARREL_ROLL_DISPLACEMENT,
+        ),
+    )
 }
 
-fn spaceship_movement_controls(
-    mut query: Query<(&mut Transform, &mut Velocity), With<Spaceship>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    time: Res<Time>,
+/// Where `player` starts, with the players spread out side by side around
+/// `STARTING_TRANSLATION`.
+fn player_start(player: Player, player_count: u8) -> Vec3 {
+    let offset = player.0 as f32 - (player_count - 1) as f32 / 2.0;
+    STARTING_TRANSLATION + Vec3::X * offset * PLAYER_SPACING
+}
+
+/// Spawns ships of the class each player selected, with the bought upgrades applied and marked
+/// with the player's colour.
+#[derive(SystemParam)]
+struct ShipSpawner<'w> {
+    selected_ships: Res<'w, SelectedShips>,
+    definitions: Res<'w, Assets<ShipDefinition>>,
+    progression: Res<'w, Progression>,
+    player_markers: Res<'w, PlayerMarkers>,
+}
+
+impl ShipSpawner<'_> {
+    fn spawn<'a>(
+        &self,
+        commands: &'a mut Commands,
+        player: Player,
+        translation: Vec3,
+    ) -> Option<EntityCommands<'a>> {
+        let Some(definition) = self
+            .selected_ships
+            .ships
+            .get(&player)
+            .and_then(|handle| self.definitions.get(handle))
+        else {
+            warn!("no ship selected for {:?}", player);
+            return None;
+        };
+
+        let mut ship = commands.spawn((
+            spaceship_bundle(definition, self.progression.ship_upgrades(), translation),
+            player,
+            Faction::Player,
+            DespawnOnRunEnd,
+        ));
+        ship.with_children(|parent| {
+            parent.spawn(PbrBundle {
+                mesh: self.player_markers.mesh.clone(),
+                material: self
+                    .player_markers
+                    .materials
+                    .get(&player)
+                    .cloned()
+                    .unwrap_or_default(),
+                transform: Transform::from_xyz(0.0, PLAYER_MARKER_OFFSET, 0.0),
+                ..default()
+            });
+        });
+        Some(ship)
+    }
+}
+
+fn create_player_markers(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(PlayerMarkers {
+        mesh: meshes.add(Torus::new(
+            PLAYER_MARKER_RADIUS - PLAYER_MARKER_THICKNESS,
+            PLAYER_MARKER_RADIUS,
+        )),
+        materials: (0..MAX_LOCAL_PLAYERS)
+            .map(Player)
+            .map(|player| {
+                let material = materials.add(StandardMaterial {
+                    base_color: player.color(),
+                    emissive: player.color(),
+                    unlit: true,
+                    ..default()
+                });
+                (player, material)
+            })
+            .collect(),
+    });
+}
+
+fn spawn_spaceships(
+    mut commands: Commands,
+    mut lives: ResMut<Lives>,
+    mut respawn_timers: ResMut<RespawnTimers>,
+    ship_spawner: ShipSpawner,
+    settings: Res<Settings>,
+) {
+    lives.remaining.clear();
+    respawn_timers.pending.clear();
+    for player in settings.players() {
+        let translation = player_start(player, settings.player_count());
+        ship_spawner.spawn(&mut commands, player, translation);
+        lives.remaining.insert(player, STARTING_LIVES);
+    }
+}
+
+fn spaceship_destroyed(
+    mut run_over_event_writer: EventWriter<RunOver>,
+    mut lives: ResMut<Lives>,
+    mut respawn_timers: ResMut<RespawnTimers>,
+    query: Query<(&Health, &Player), With<Spaceship>>,
+    settings: Res<Settings>,
 ) {
-    let Ok((mut transform, mut velocity)) = query.get_single_mut() else {
+    // despawn_dead_entities removes ships at the end of this set, so this sees each one once
+    let mut any_destroyed = false;
+    for (health, &player) in query.iter() {
+        if health.value > 0.0 {
+            continue;
+        }
+        any_destroyed = true;
+
+        let remaining = lives.remaining.entry(player).or_default();
+        *remaining = remaining.saturating_sub(1);
+        if *remaining > 0 {
+            respawn_timers.pending.insert(
+                player,
+                Timer::from_seconds(RESPAWN_DELAY_SECONDS, TimerMode::Once),
+            );
+        }
+    }
+    if !any_destroyed {
         return;
+    }
+
+    let players_left = lives
+        .remaining
+        .values()
+        .filter(|&&remaining| remaining > 0)
+        .count();
+    // versus ends with one survivor, unless there was only ever one player
+    let game_over = match settings.match_mode {
+        MatchMode::Coop => players_left == 0,
+        MatchMode::Versus => players_left == 0 || (lives.remaining.len() > 1 && players_left <= 1),
     };
-    let mut rotation = 0.0;
-    let mut roll = 0.0;
-    let mut movement = 0.0;
-
-    // barrel roll
-    if keyboard_input.pressed(KeyCode::KeyK) {
-        roll = -ROLL_SPEED * time.delta_seconds();
-    } else if keyboard_input.pressed(KeyCode::KeyL) {
-        roll = ROLL_SPEED * time.delta_seconds();
+    if game_over {
+        run_over_event_writer.send(RunOver);
     }
+}
 
-    // y-axis rotate
-    if keyboard_input.pressed(KeyCode::KeyD) {
-        rotation = -ROTATION_SPEED * time.delta_seconds();
-    } else if keyboard_input.pressed(KeyCode::KeyA) {
-        rotation = ROTATION_SPEED * time.delta_seconds();
+fn respawn_spaceship(
+    mut commands: Commands,
+    mut respawn_timers: ResMut<RespawnTimers>,
+    collider_query: Query<(&GlobalTransform, &Collider)>,
+    ship_spawner: ShipSpawner,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    let mut ready = vec![];
+    for (&player, timer) in respawn_timers.pending.iter_mut() {
+        if timer.tick(time.delta()).finished() {
+            ready.push(player);
+        }
     }
 
-    // forward/reverse movement
-    if keyboard_input.pressed(KeyCode::KeyS) {
-        movement = -MOVEMENT_SPEED;
-    } else if keyboard_input.pressed(KeyCode::KeyW) {
-        movement = MOVEMENT_SPEED;
+    for player in ready {
+        respawn_timers.pending.remove(&player);
+        let origin = player_start(player, settings.player_count());
+        let translation = safest_respawn_point(origin, &collider_query);
+        if let Some(mut ship) = ship_spawner.spawn(&mut commands, player, translation) {
+            ship.insert(Invulnerable::new(RESPAWN_INVULNERABLE_SECONDS));
+        }
     }
+}
+
+/// Prefers `origin`, falling back to rings of points around it. Returns the first point with at
+/// least `RESPAWN_SAFE_DISTANCE` of clearance, or the most clear one if none are.
+fn safest_respawn_point(
+    origin: Vec3,
+    collider_query: &Query<(&GlobalTransform, &Collider)>,
+) -> Vec3 {
+    let clearance = |point: Vec3| {
+        collider_query
+            .iter()
+            .map(|(transform, collider)| transform.translation().distance(point) - collider.radius)
+            .fold(f32::INFINITY, f32::min)
+    };
+
+    let candidates =
+        std::iter::once(origin).chain(RESPAWN_SEARCH_RINGS.iter().flat_map(|&radius| {
+            (0..RESPAWN_SEARCH_DIRECTIONS).map(move |i| {
+                let angle = i as f32 / RESPAWN_SEARCH_DIRECTIONS as f32 * std::f32::consts::TAU;
+                origin + Vec3::new(angle.cos(), 0.0, angle.sin()) * radius
+            })
+        }));
+
+    let mut safest = (origin, f32::NEG_INFINITY);
+    for candidate in candidates {
+        let candidate_clearance = clearance(candidate);
+        if candidate_clearance >= RESPAWN_SAFE_DISTANCE {
+            return candidate;
+        }
+        if candidate_clearance > safest.1 {
+            safest = (candidate, candidate_clearance);
+        }
+    }
+    safest.0
+}
 
-    transform.rotate_y(rotation);
-    transform.rotate_local_z(roll);
-    velocity.value = -transform.forward() * movement;
+fn control_scheme_is(control_scheme: ControlScheme) -> impl Fn(Res<Settings>) -> bool {
+    move |settings: Res<Settings>| settings.control_scheme == control_scheme
 }
 
-fn spaceship_weapon_controls(
-    mut commands: Commands,
-    query: Query<&Transform, With<Spaceship>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    scene_assets: Res<SceneAssets>,
+/// Overrides the first player's turn input to steer their ship towards the mouse cursor, turning
+/// no faster than its rotation speed and easing off as the nose lines up.
+fn mouse_aim_controls(
+    mut query: Query<
+        (&mut ShipControls, &Transform, &ShipStats, &Player),
+        With<KeyboardControlled>,
+    >,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    time: Res<Time>,
 ) {
-    let Ok(transform) = query.get_single() else {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    let Some(target) = window
+        .cursor_position()
+        .and_then(|cursor| cursor_to_gameplay_plane(camera, camera_transform, cursor))
+    else {
         return;
     };
 
-    if keyboard_input.pressed(KeyCode::Space) {
-        let missile_transform = Transform::from_translation(
-            transform.translation + -transform.forward() * MISSILE_FORWARD_SPAWN,
-        )
-        .with_rotation(transform.rotation)
-        .with_scale(Vec3::splat(0.25));
-
-        commands.spawn((
-            MovingObjectBundle {
-                acceleration: Acceleration::new(Vec3::ZERO),
-                collider: Collider::new(MISSILE_RADIUS),
-                velocity: Velocity::new(-transform.forward() * MISSILE_SPEED),
-                model: SceneBundle {
-                    scene: scene_assets.missile.clone(),
-                    transform: missile_transform,
-                    ..default()
-                },
-            },
-            SpaceshipMissile,
-            DespawnWhenRemote,
-            Health::new(MISSILE_HEALTH),
-            CollisionDamage::new(MISSILE_COLLISION_DAMAGE),
-        ));
+    for (mut controls, transform, stats, &player) in query.iter_mut() {
+        if player != Player(0) {
+            continue;
+        }
+        let Some(angle) = angle_to(transform, target) else {
+            continue;
+        };
+        controls.turn = turn_towards(angle, stats.rotation_speed, time.delta_seconds());
+    }
+}
+
+/// The angle from the ship's nose to `target` on the gameplay plane, positive
+/// counter-clockwise around +y (i.e. to the left). `None` if the target is on top of the ship.
+pub fn angle_to(transform: &Transform, target: Vec3) -> Option<f32> {
+    let nose = -*transform.forward();
+    let mut to_target = target - transform.translation;
+    to_target.y = 0.0;
+    if to_target.length_squared() < f32::EPSILON {
+        return None;
+    }
+    Some(nose.cross(to_target).y.atan2(nose.dot(to_target)))
+}
+
+/// The turn input that closes `angle` (from `angle_to`) this frame without overshooting.
+pub fn turn_towards(angle: f32, rotation_speed: f32, delta_seconds: f32) -> f32 {
+    let max_rotation = rotation_speed * delta_seconds;
+    (-angle / max_rotation).clamp(-1.0, 1.0)
+}
+
+#[allow(clippy::type_complexity)]
+fn ship_movement_controls(
+    mut query: Query<(
+        (&ShipControls, &ShipStats),
+        &mut Transform,
+        &mut Velocity,
+        Option<&Strafe>,
+        (Option<&Boost>, Option<&SpeedBoost>),
+        Option<&mut Roll>,
+    )>,
+    time: Res<Time>,
+) {
+    for ((controls, stats), mut transform, mut velocity, strafe, (boost, speed_boost), roll) in
+        query.iter_mut()
+    {
+        // barrel roll, applied by the roll system so it can track completed rolls
+        if let Some(mut roll) = roll {
+            roll.input = controls.roll;
+        }
+
+        // y-axis rotate
+        let rotation =
+            -controls.turn.clamp(-1.0, 1.0) * stats.rotation_speed * time.delta_seconds();
+
+        // forward/reverse movement
+        let movement = controls.thrust.clamp(-1.0, 1.0) * stats.movement_speed;
+
+        // left/right strafe
+        let strafing = strafe.map_or(0.0, |strafe| {
+            controls.strafe.clamp(-1.0, 1.0) * strafe.speed
+        });
+
+        let speed_multiplier = boost.map_or(1.0, Boost::speed_multiplier)
+            * speed_boost.map_or(1.0, |speed_boost| speed_boost.multiplier);
+
+        transform.rotate_y(rotation);
+        velocity.value = (-transform.forward() * movement + planar_right(&transform) * strafing)
+            * speed_multiplier;
+    }
+}
+
+fn ship_weapon_controls(
+    mut fire_weapon_event_writer: EventWriter<FireWeaponEvent>,
+    query: Query<(Entity, &ShipControls)>,
+) {
+    for (entity, controls) in query.iter() {
+        if controls.fire {
+            fire_weapon_event_writer.send(FireWeaponEvent::new(entity));
+        }
+    }
+}
+
+fn ship_ability_controls(
+    mut use_ability_event_writer: EventWriter<UseAbilityEvent>,
+    query: Query<(Entity, &ShipControls, &Transform)>,
+) {
+    for (entity, controls, transform) in query.iter() {
+        if controls.boost {
+            use_ability_event_writer.send(UseAbilityEvent::new(entity, Ability::Boost));
+        }
+
+        if controls.dash {
+            // dash in the strafe direction if strafing, otherwise straight ahead
+            let direction = if controls.strafe != 0.0 {
+                planar_right(transform) * controls.strafe.signum()
+            } else {
+                -*transform.forward()
+            };
+            use_ability_event_writer
+                .send(UseAbilityEvent::new(entity, Ability::Dash { direction }));
+        }
     }
 }
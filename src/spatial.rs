@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+/// A uniform grid over the gameplay plane for finding what's near a point without checking
+/// everything. Each entry is a circle, filed under every cell its bounds overlap, so entries
+/// can be of any size.
+///
+/// Moving entries are refiled every frame. Static entries are kept apart, so they survive
+/// `clear` and are only filed again when they're moved.
+#[derive(Resource, Debug)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<SpatialEntry>>,
+    static_cells: HashMap<IVec2, Vec<SpatialEntry>>,
+    statics: HashMap<Entity, SpatialEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpatialEntry {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub radius: f32,
+    pub is_static: bool,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            static_cells: HashMap::new(),
+            statics: HashMap::new(),
+        }
+    }
+
+    /// Removes every moving entry.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec3, radius: f32) {
+        let entry = SpatialEntry {
+            entity,
+            position,
+            radius,
+            is_static: false,
+        };
+        for cell in self.cells_overlapping(position, radius) {
+            self.cells.entry(cell).or_default().push(entry);
+        }
+    }
+
+    /// Files an entry that stays put until it's removed or inserted again somewhere else.
+    pub fn insert_static(&mut self, entity: Entity, position: Vec3, radius: f32) {
+        self.remove_static(entity);
+        let entry = SpatialEntry {
+            entity,
+            position,
+            radius,
+            is_static: true,
+        };
+        for cell in self.cells_overlapping(position, radius) {
+            self.static_cells.entry(cell).or_default().push(entry);
+        }
+        self.statics.insert(entity, entry);
+    }
+
+    pub fn remove_static(&mut self, entity: Entity) {
+        let Some(entry) = self.statics.remove(&entity) else {
+            return;
+        };
+        for cell in self.cells_overlapping(entry.position, entry.radius) {
+            if let Some(entries) = self.static_cells.get_mut(&cell) {
+                entries.retain(|entry| entry.entity != entity);
+            }
+        }
+    }
+
+    /// Every entry whose circle overlaps the circle of `radius` around `position`, once each.
+    pub fn query(&self, position: Vec3, radius: f32) -> Vec<SpatialEntry> {
+        let mut entries: Vec<SpatialEntry> = self
+            .cells_overlapping(position, radius)
+            .flat_map(|cell| [self.cells.get(&cell), self.static_cells.get(&cell)])
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.position.distance(position) < entry.radius + radius)
+            .copied()
+            .collect();
+        // entries spanning several cells are found once per cell
+        entries.sort_unstable_by_key(|entry| entry.entity);
+        entries.dedup_by_key(|entry| entry.entity);
+        entries
+    }
+
+    fn cell(&self, position: Vec3) -> IVec2 {
+        IVec2::new(
+            (position.x / self.cell_size).floor() as i32,
+            (position.z / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn cells_overlapping(&self, position: Vec3, radius: f32) -> impl Iterator<Item = IVec2> {
+        let min = self.cell(position - Vec3::splat(radius));
+        let max = self.cell(position + Vec3::splat(radius));
+        (min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+    }
+}
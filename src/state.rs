@@ -1,10 +1,51 @@
+use bevy::ecs::event::ManualEventReader;
+use bevy::ecs::schedule::ScheduleLabel;
 use bevy::prelude::*;
+use bevy::utils::Duration;
+use bevy::window::WindowFocused;
+
+use crate::action::{Action, ActionState};
+use crate::schedule::InGameSet;
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum GameState {
     #[default]
+    Loading,
+    MainMenu,
+    HighScores,
+    ShipSelection,
     InGame,
     Paused,
+    GameOver,
+}
+
+impl GameState {
+    /// Whether the arena is in use. A run carries on through pausing and stays on screen behind
+    /// the game over screen, so its entities are only cleared away once it's left for good.
+    pub fn in_run(&self) -> bool {
+        matches!(
+            self,
+            GameState::InGame | GameState::Paused | GameState::GameOver
+        )
+    }
+}
+
+/// Runs as play starts, whether from ship selection or straight after the last run ended.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OnRunStart;
+
+/// Runs when a run is left, before anything else starts.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OnRunEnd;
+
+/// Throws the current run away and starts a new one with the same ships, from the pause menu.
+#[derive(Event, Debug)]
+pub struct RestartRun;
+
+/// How long the current run has been played, not counting time spent paused.
+#[derive(Resource, Debug, Default)]
+pub struct RunTime {
+    pub elapsed: Duration,
 }
 
 pub struct StatePlugin;
@@ -12,19 +53,326 @@ pub struct StatePlugin;
 impl Plugin for StatePlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<GameState>()
-            .add_systems(Update, game_state_input_events);
+            .init_schedule(OnRunStart)
+            .init_schedule(OnRunEnd)
+            .init_resource::<RunTime>()
+            .add_event::<RestartRun>()
+            .add_systems(
+                StateTransition,
+                (
+                    restart_runs.before(apply_state_transition::<GameState>),
+                    run_transitions.after(apply_state_transition::<GameState>),
+                ),
+            )
+            .add_systems(OnRunStart, reset_run_time)
+            .add_systems(Update, tick_run_time.in_set(InGameSet::EntityUpdates))
+            .add_systems(
+                Update,
+                (
+                    game_state_input_events
+                        .run_if(in_state(GameState::InGame).or_else(in_state(GameState::Paused))),
+                    pause_on_focus_lost.run_if(in_state(GameState::InGame)),
+                ),
+            );
+    }
+}
+
+/// Runs `OnRunEnd` and `OnRunStart` around state transitions that leave or begin a run.
+/// Playing again from the game over screen runs both.
+fn run_transitions(
+    world: &mut World,
+    mut reader: Local<ManualEventReader<StateTransitionEvent<GameState>>>,
+) {
+    let transitions: Vec<_> = reader
+        .read(world.resource::<Events<StateTransitionEvent<GameState>>>())
+        .copied()
+        .collect();
+    for StateTransitionEvent { before, after } in transitions {
+        let restarting = before == GameState::GameOver;
+        if before.in_run() && (restarting || !after.in_run()) {
+            world.run_schedule(OnRunEnd);
+        }
+        if after.in_run() && (restarting || !before.in_run()) {
+            world.run_schedule(OnRunStart);
+        }
     }
 }
 
+/// Clears the run away and sets it up afresh, then carries on playing. Pausing and resuming
+/// stay within the run, so this runs both schedules itself.
+fn restart_runs(world: &mut World, mut reader: Local<ManualEventReader<RestartRun>>) {
+    if reader.read(world.resource::<Events<RestartRun>>()).count() == 0 {
+        return;
+    }
+    world.run_schedule(OnRunEnd);
+    world.run_schedule(OnRunStart);
+    world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::InGame);
+}
+
+fn reset_run_time(mut run_time: ResMut<RunTime>) {
+    *run_time = RunTime::default();
+}
+
+fn tick_run_time(mut run_time: ResMut<RunTime>, time: Res<Time>) {
+    run_time.elapsed += time.delta();
+}
+
 fn game_state_input_events(
     mut next_state: ResMut<NextState<GameState>>,
     state: Res<State<GameState>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    action_state: Res<ActionState>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    if action_state.any_just_pressed(Action::Pause) {
         match state.get() {
             GameState::InGame => next_state.set(GameState::Paused),
             GameState::Paused => next_state.set(GameState::InGame),
+            GameState::Loading
+            | GameState::MainMenu
+            | GameState::HighScores
+            | GameState::ShipSelection
+            | GameState::GameOver => (),
         }
     }
 }
+
+fn pause_on_focus_lost(
+    mut window_focused_event_reader: EventReader<WindowFocused>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if window_focused_event_reader
+        .read()
+        .any(|event| !event.focused)
+    {
+        next_state.set(GameState::Paused);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::AssetPlugin;
+    use bevy::input::InputPlugin;
+    use bevy::time::TimeUpdateStrategy;
+    use bevy::utils::Duration;
+
+    use crate::ability::AbilityPlugin;
+    use crate::action::{ActionMap, ActionPlugin};
+    use crate::asset_loader::AssetLoaderPlugin;
+    use crate::asteroid::{Asteroid, AsteroidPlugin};
+    use crate::boss::BossPlugin;
+    use crate::collision::CollisionPlugin;
+    use crate::controls::ControlsPlugin;
+    use crate::despawn::DespawnPlugin;
+    use crate::enemy::EnemyPlugin;
+    use crate::faction::FactionPlugin;
+    use crate::game_over::{GameOverPlugin, RunSummaryPath};
+    use crate::health::{Health, HealthPlugin};
+    use crate::movement::MovementPlugin;
+    use crate::powerup::PowerUpPlugin;
+    use crate::progression::Progression;
+    use crate::rng::RngPlugin;
+    use crate::schedule::SchedulePlugin;
+    use crate::score::ScorePlugin;
+    use crate::settings::{Settings, SettingsPlugin};
+    use crate::shield::ShieldPlugin;
+    use crate::ship_definition::{ShipDefinition, ShipDefinitionPlugin, WeaponDefinition};
+    use crate::ship_selection::{SelectedShips, ShipSelectionPlugin};
+    use crate::spaceship::{Lives, RespawnTimers, Spaceship, SpaceshipPlugin};
+    use crate::steering::SteeringPlugin;
+    use crate::structure::StructurePlugin;
+    use crate::wave::WavePlugin;
+    use crate::weapon::WeaponPlugin;
+
+    const FRAME_SECONDS: f32 = 1.0 / 60.0;
+    const PLAY_FRAMES: usize = 600;
+    const GAME_OVER_FRAMES: usize = 180;
+
+    /// The gameplay plugins without a window or renderer, stepping a fixed time each update.
+    /// Settings and bindings are the defaults, progression and high scores are left out, and the
+    /// run summary isn't written, so the test never touches the player's files.
+    fn headless_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            TransformPlugin,
+            HierarchyPlugin,
+            InputPlugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .init_asset::<Scene>()
+        // sent by the window plugin, which needs a display
+        .add_event::<WindowFocused>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            FRAME_SECONDS,
+        )))
+        .insert_resource(Settings::default())
+        .insert_resource(ActionMap::default())
+        .insert_resource(Progression::default())
+        .insert_resource(RunSummaryPath(None))
+        .add_plugins((
+            AssetLoaderPlugin,
+            SettingsPlugin,
+            ActionPlugin,
+            StatePlugin,
+            RngPlugin,
+            ShipDefinitionPlugin,
+            ShipSelectionPlugin,
+            ControlsPlugin,
+            DespawnPlugin,
+            CollisionPlugin,
+            FactionPlugin,
+            HealthPlugin,
+            ShieldPlugin,
+            MovementPlugin,
+            SteeringPlugin,
+        ))
+        .add_plugins((
+            AsteroidPlugin,
+            SpaceshipPlugin,
+            EnemyPlugin,
+            BossPlugin,
+            StructurePlugin,
+            WavePlugin,
+            ScorePlugin,
+            GameOverPlugin,
+            WeaponPlugin,
+            AbilityPlugin,
+            PowerUpPlugin,
+            SchedulePlugin,
+        ));
+        app.update();
+
+        // every player flies the same ship, so nothing waits on the ships folder loading
+        let definition = app
+            .world
+            .resource_mut::<Assets<ShipDefinition>>()
+            .add(ShipDefinition {
+                name: "Test".to_string(),
+                model: Handle::default(),
+                collider_radius: 2.5,
+                movement_speed: 25.0,
+                rotation_speed: 2.5,
+                health: 100.0,
+                weapon: WeaponDefinition {
+                    seconds_between_shots: 0.2,
+                    heat_per_shot: 0.0,
+                    energy_per_shot: 0.0,
+                    ammo: None,
+                    homing_acceleration: None,
+                },
+            });
+        let players: Vec<_> = app.world.resource::<Settings>().players().collect();
+        let mut selected_ships = app.world.resource_mut::<SelectedShips>();
+        for player in players {
+            selected_ships.ships.insert(player, definition.clone());
+        }
+        app
+    }
+
+    fn set_state(app: &mut App, state: GameState) {
+        app.world.resource_mut::<NextState<GameState>>().set(state);
+        app.update();
+    }
+
+    /// Every entity as its sorted component names, sorted, so two worlds holding the same kinds
+    /// of thing compare equal whatever their entity ids.
+    fn entity_set(world: &World) -> Vec<Vec<String>> {
+        let mut entities: Vec<Vec<String>> = world
+            .iter_entities()
+            .map(|entity| {
+                let mut components: Vec<String> = world
+                    .inspect_entity(entity.id())
+                    .into_iter()
+                    .map(|info| info.name().to_string())
+                    .collect();
+                components.sort();
+                components
+            })
+            .collect();
+        entities.sort();
+        entities
+    }
+
+    fn count<T: Component>(app: &mut App) -> usize {
+        app.world
+            .query_filtered::<(), With<T>>()
+            .iter(&app.world)
+            .count()
+    }
+
+    #[test]
+    fn restarting_returns_to_the_starting_entity_set() {
+        let mut app = headless_app();
+        set_state(&mut app, GameState::InGame);
+        let starting = entity_set(&app.world);
+        assert!(count::<Spaceship>(&mut app) > 0);
+
+        for _ in 0..PLAY_FRAMES {
+            app.update();
+        }
+        assert!(count::<Asteroid>(&mut app) > 0);
+
+        // a ship destroyed during play has to be back before they can all be destroyed at once
+        while !app.world.resource::<RespawnTimers>().pending.is_empty() {
+            app.update();
+        }
+        // down to the last life, then destroy every ship
+        for remaining in app.world.resource_mut::<Lives>().remaining.values_mut() {
+            *remaining = 1;
+        }
+        let mut ships = app.world.query_filtered::<&mut Health, With<Spaceship>>();
+        for mut health in ships.iter_mut(&mut app.world) {
+            health.value = 0.0;
+        }
+        // through the slow-motion beat to the game over screen
+        for _ in 0..GAME_OVER_FRAMES {
+            app.update();
+        }
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().get(),
+            GameState::GameOver
+        );
+        assert!(app.world.resource::<Time<Virtual>>().is_paused());
+
+        set_state(&mut app, GameState::InGame);
+        assert_eq!(entity_set(&app.world), starting);
+        assert!(!app.world.resource::<Time<Virtual>>().is_paused());
+    }
+
+    #[test]
+    fn losing_focus_pauses_and_restarting_from_pause_starts_afresh() {
+        let mut app = headless_app();
+        set_state(&mut app, GameState::InGame);
+        let starting = entity_set(&app.world);
+
+        for _ in 0..PLAY_FRAMES {
+            app.update();
+        }
+        assert!(count::<Asteroid>(&mut app) > 0);
+
+        app.world.send_event(WindowFocused {
+            window: Entity::PLACEHOLDER,
+            focused: false,
+        });
+        app.update();
+        app.update();
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().get(),
+            GameState::Paused
+        );
+
+        app.world.send_event(RestartRun);
+        app.update();
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().get(),
+            GameState::InGame
+        );
+        assert_eq!(entity_set(&app.world), starting);
+        // the frame that restarted it has already been played
+        assert!(app.world.resource::<RunTime>().elapsed <= Duration::from_secs_f32(FRAME_SECONDS));
+    }
+}
@@ -0,0 +1,689 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::collision::Collider;
+use crate::controls::ControlSourceSet;
+use crate::movement::{Acceleration, Velocity};
+use crate::rng::GameRng;
+use crate::schedule::InGameSet;
+use crate::spatial::SpatialGrid;
+
+/// The velocity a target is assumed to have when it has none.
+const STATIONARY: Vec3 = Vec3::ZERO;
+
+/// One way of steering. Each turns the current motion and a target into a steering force,
+/// i.e. the change of velocity wanted this second.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum SteeringBehaviour {
+    /// Head straight for the target at full speed.
+    Seek,
+    /// Head straight away from the target at full speed.
+    Flee,
+    /// Head for a point `standoff` short of the target, slowing down within `slowing_radius`
+    /// of it.
+    Arrive { slowing_radius: f32, standoff: f32 },
+    /// Seek where a moving target will be by the time we get there.
+    Pursue,
+    /// Flee from where a moving target will be.
+    Evade,
+    /// Meander, by seeking a point on a circle of `radius` held `distance` ahead, which moves
+    /// around the circle by up to `jitter` radians a second.
+    Wander {
+        distance: f32,
+        radius: f32,
+        jitter: f32,
+    },
+    /// Swerve around `Collider`s within `look_ahead` of the current heading.
+    AvoidObstacles { look_ahead: f32 },
+    /// Keep clear of other members of the flock within `radius`.
+    Separate { radius: f32 },
+    /// Head the same way as other members of the flock within `radius`.
+    Align { radius: f32 },
+    /// Head for the middle of the other members of the flock within `radius`.
+    Cohere { radius: f32 },
+}
+
+impl SteeringBehaviour {
+    /// How far away other members of the flock affect this behaviour, if they do.
+    pub fn flocking_radius(&self) -> Option<f32> {
+        match *self {
+            Self::Separate { radius } | Self::Align { radius } | Self::Cohere { radius } => {
+                Some(radius)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Steers the entity by setting its `Acceleration` to the weighted sum of its behaviours,
+/// clamped to `max_acceleration` and to what keeps it under `max_speed`.
+#[derive(Component, Debug, Clone)]
+pub struct Steering {
+    pub behaviours: Vec<(SteeringBehaviour, f32)>,
+    pub target: Option<Entity>,
+    pub max_speed: f32,
+    pub max_acceleration: f32,
+    /// Where the wander point currently is on its circle.
+    pub wander_angle: f32,
+}
+
+impl Steering {
+    pub fn new(max_speed: f32, max_acceleration: f32) -> Self {
+        Self {
+            behaviours: vec![],
+            target: None,
+            max_speed,
+            max_acceleration,
+            wander_angle: 0.0,
+        }
+    }
+
+    pub fn with_behaviour(mut self, behaviour: SteeringBehaviour, weight: f32) -> Self {
+        self.behaviours.push((behaviour, weight));
+        self
+    }
+
+    pub fn with_behaviours(mut self, behaviours: &[(SteeringBehaviour, f32)]) -> Self {
+        self.behaviours.extend_from_slice(behaviours);
+        self
+    }
+
+    pub fn with_target(mut self, target: Entity) -> Self {
+        self.target = Some(target);
+        self
+    }
+}
+
+/// Makes a steered entity point its nose (local +Z) along its velocity, for things like missiles
+/// that don't turn by any other means.
+#[derive(Component, Debug)]
+pub struct FaceVelocity;
+
+/// Marks a steered entity as part of a flock. The flocking behaviours only take other members
+/// into account.
+#[derive(Component, Debug)]
+pub struct Flock;
+
+pub struct SteeringPlugin;
+
+impl Plugin for SteeringPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                apply_steering
+                    .before(ControlSourceSet)
+                    .in_set(InGameSet::UserInput),
+                face_velocity.in_set(InGameSet::EntityUpdates),
+            ),
+        );
+    }
+}
+
+pub fn seek(position: Vec3, velocity: Vec3, target: Vec3, max_speed: f32) -> Vec3 {
+    let desired = (target - position).normalize_or_zero() * max_speed;
+    desired - velocity
+}
+
+pub fn flee(position: Vec3, velocity: Vec3, target: Vec3, max_speed: f32) -> Vec3 {
+    let desired = (position - target).normalize_or_zero() * max_speed;
+    desired - velocity
+}
+
+pub fn arrive(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    max_speed: f32,
+    slowing_radius: f32,
+    standoff: f32,
+) -> Vec3 {
+    let offset = target - position;
+    let distance = offset.length() - standoff;
+    if offset.length_squared() < f32::EPSILON {
+        return -velocity;
+    }
+    let speed = if distance.abs() < slowing_radius {
+        max_speed * distance / slowing_radius
+    } else {
+        max_speed * distance.signum()
+    };
+    offset.normalize() * speed - velocity
+}
+
+/// Where a target moving at constant velocity will be when something at `position` moving at
+/// `max_speed` could reach its current position.
+fn predict(position: Vec3, target: Vec3, target_velocity: Vec3, max_speed: f32) -> Vec3 {
+    if max_speed <= 0.0 {
+        return target;
+    }
+    let time = position.distance(target) / max_speed;
+    target + target_velocity * time
+}
+
+pub fn pursue(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    target_velocity: Vec3,
+    max_speed: f32,
+) -> Vec3 {
+    let prediction = predict(position, target, target_velocity, max_speed);
+    seek(position, velocity, prediction, max_speed)
+}
+
+pub fn evade(
+    position: Vec3,
+    velocity: Vec3,
+    target: Vec3,
+    target_velocity: Vec3,
+    max_speed: f32,
+) -> Vec3 {
+    let prediction = predict(position, target, target_velocity, max_speed);
+    flee(position, velocity, prediction, max_speed)
+}
+
+/// Seeks the point at `angle` on a circle of `radius`, `distance` ahead along the current
+/// heading. The caller moves `angle` a little each frame.
+pub fn wander(
+    position: Vec3,
+    velocity: Vec3,
+    max_speed: f32,
+    distance: f32,
+    radius: f32,
+    angle: f32,
+) -> Vec3 {
+    let heading = velocity.try_normalize().unwrap_or(Vec3::Z);
+    let circle_center = position + heading * distance;
+    let target = circle_center + Vec3::new(angle.cos(), 0.0, angle.sin()) * radius;
+    seek(position, velocity, target, max_speed)
+}
+
+/// Pushes sideways away from the nearest obstacle, given as `(center, radius)`, that the current
+/// heading would hit within `look_ahead`, harder the closer it is.
+pub fn avoid_obstacles(
+    position: Vec3,
+    velocity: Vec3,
+    radius: f32,
+    max_speed: f32,
+    look_ahead: f32,
+    obstacles: impl IntoIterator<Item = (Vec3, f32)>,
+) -> Vec3 {
+    let Some(heading) = velocity.try_normalize() else {
+        return Vec3::ZERO;
+    };
+
+    let mut nearest: Option<(f32, Vec3)> = None;
+    for (center, obstacle_radius) in obstacles {
+        let to_obstacle = center - position;
+        let ahead = to_obstacle.dot(heading);
+        if ahead <= 0.0 || ahead > look_ahead {
+            continue;
+        }
+        let lateral = to_obstacle - heading * ahead;
+        if lateral.length() >= obstacle_radius + radius {
+            continue;
+        }
+        if nearest.is_none_or(|(nearest_ahead, _)| ahead < nearest_ahead) {
+            nearest = Some((ahead, lateral));
+        }
+    }
+
+    let Some((ahead, lateral)) = nearest else {
+        return Vec3::ZERO;
+    };
+    // dead ahead: pick a side
+    let away = (-lateral)
+        .try_normalize()
+        .unwrap_or_else(|| heading.cross(Vec3::Y).normalize_or_zero());
+    away * max_speed * (1.0 - ahead / look_ahead)
+}
+
+/// Pushes away from neighbours, given as `(position, velocity)`, closer than `radius`, harder
+/// the closer they are.
+pub fn separate(
+    position: Vec3,
+    velocity: Vec3,
+    max_speed: f32,
+    radius: f32,
+    neighbours: &[(Vec3, Vec3)],
+) -> Vec3 {
+    let mut push = Vec3::ZERO;
+    for &(neighbour, _) in neighbours {
+        let offset = position - neighbour;
+        let distance = offset.length();
+        if distance >= radius || distance <= f32::EPSILON {
+            continue;
+        }
+        push += offset / distance * (1.0 - distance / radius);
+    }
+    match push.try_normalize() {
+        Some(direction) => direction * max_speed - velocity,
+        None => Vec3::ZERO,
+    }
+}
+
+/// Matches the average heading of neighbours within `radius`.
+pub fn align(
+    position: Vec3,
+    velocity: Vec3,
+    max_speed: f32,
+    radius: f32,
+    neighbours: &[(Vec3, Vec3)],
+) -> Vec3 {
+    let heading: Vec3 = neighbours
+        .iter()
+        .filter(|(neighbour, _)| neighbour.distance(position) < radius)
+        .map(|&(_, neighbour_velocity)| neighbour_velocity)
+        .sum();
+    match heading.try_normalize() {
+        Some(direction) => direction * max_speed - velocity,
+        None => Vec3::ZERO,
+    }
+}
+
+/// Seeks the average position of neighbours within `radius`.
+pub fn cohere(
+    position: Vec3,
+    velocity: Vec3,
+    max_speed: f32,
+    radius: f32,
+    neighbours: &[(Vec3, Vec3)],
+) -> Vec3 {
+    let nearby: Vec<Vec3> = neighbours
+        .iter()
+        .map(|&(neighbour, _)| neighbour)
+        .filter(|neighbour| neighbour.distance(position) < radius)
+        .collect();
+    if nearby.is_empty() {
+        return Vec3::ZERO;
+    }
+    let center = nearby.iter().sum::<Vec3>() / nearby.len() as f32;
+    seek(position, velocity, center, max_speed)
+}
+
+/// Adds up weighted steering forces and clamps the result to `max_acceleration`.
+pub fn combine(forces: impl IntoIterator<Item = (Vec3, f32)>, max_acceleration: f32) -> Vec3 {
+    forces
+        .into_iter()
+        .map(|(force, weight)| force * weight)
+        .sum::<Vec3>()
+        .clamp_length_max(max_acceleration)
+}
+
+fn apply_steering(
+    mut query: Query<(
+        Entity,
+        &mut Steering,
+        &Transform,
+        &Velocity,
+        &mut Acceleration,
+    )>,
+    mut rng: ResMut<GameRng>,
+    target_query: Query<(&GlobalTransform, Option<&Velocity>)>,
+    collider_query: Query<&Collider>,
+    flock_query: Query<&Velocity, With<Flock>>,
+    grid: Res<SpatialGrid>,
+    time: Res<Time>,
+) {
+    for (entity, mut steering, transform, velocity, mut acceleration) in query.iter_mut() {
+        let jitter: f32 = steering
+            .behaviours
+            .iter()
+            .filter_map(|(behaviour, _)| match behaviour {
+                SteeringBehaviour::Wander { jitter, .. } => Some(*jitter),
+                _ => None,
+            })
+            .sum();
+        steering.wander_angle += rng.gen_range(-1.0..=1.0) * jitter * time.delta_seconds();
+
+        let position = transform.translation;
+        let velocity = velocity.value;
+        let max_speed = steering.max_speed;
+        let target = steering.target.and_then(|target| {
+            let (target_transform, target_velocity) = target_query.get(target).ok()?;
+            let target_velocity = target_velocity.map_or(STATIONARY, |velocity| velocity.value);
+            Some((target_transform.translation(), target_velocity))
+        });
+        let radius = collider_query
+            .get(entity)
+            .map_or(0.0, |collider| collider.radius);
+        let flocking_radius = steering
+            .behaviours
+            .iter()
+            .filter_map(|(behaviour, _)| behaviour.flocking_radius())
+            .fold(0.0, f32::max);
+        let neighbours: Vec<(Vec3, Vec3)> = if flocking_radius > 0.0 {
+            grid.query(position, flocking_radius)
+                .into_iter()
+                .filter(|entry| entry.entity != entity)
+                .filter_map(|entry| {
+                    let neighbour_velocity = flock_query.get(entry.entity).ok()?;
+                    Some((entry.position, neighbour_velocity.value))
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
+        let mut forces = vec![];
+        for &(behaviour, weight) in steering.behaviours.iter() {
+            let force = match (behaviour, target) {
+                (SteeringBehaviour::Seek, Some((target, _))) => {
+                    seek(position, velocity, target, max_speed)
+                }
+                (SteeringBehaviour::Flee, Some((target, _))) => {
+                    flee(position, velocity, target, max_speed)
+                }
+                (
+                    SteeringBehaviour::Arrive {
+                        slowing_radius,
+                        standoff,
+                    },
+                    Some((target, _)),
+                ) => arrive(
+                    position,
+                    velocity,
+                    target,
+                    max_speed,
+                    slowing_radius,
+                    standoff,
+                ),
+                (SteeringBehaviour::Pursue, Some((target, target_velocity))) => {
+                    pursue(position, velocity, target, target_velocity, max_speed)
+                }
+                (SteeringBehaviour::Evade, Some((target, target_velocity))) => {
+                    evade(position, velocity, target, target_velocity, max_speed)
+                }
+                (
+                    SteeringBehaviour::Wander {
+                        distance, radius, ..
+                    },
+                    _,
+                ) => wander(
+                    position,
+                    velocity,
+                    max_speed,
+                    distance,
+                    radius,
+                    steering.wander_angle,
+                ),
+                (SteeringBehaviour::AvoidObstacles { look_ahead }, _) => {
+                    let obstacles = grid
+                        .query(position, look_ahead + radius)
+                        .into_iter()
+                        .filter(|entry| {
+                            entry.entity != entity && Some(entry.entity) != steering.target
+                        })
+                        .map(|entry| (entry.position, entry.radius));
+                    avoid_obstacles(position, velocity, radius, max_speed, look_ahead, obstacles)
+                }
+                (SteeringBehaviour::Separate { radius }, _) => {
+                    separate(position, velocity, max_speed, radius, &neighbours)
+                }
+                (SteeringBehaviour::Align { radius }, _) => {
+                    align(position, velocity, max_speed, radius, &neighbours)
+                }
+                (SteeringBehaviour::Cohere { radius }, _) => {
+                    cohere(position, velocity, max_speed, radius, &neighbours)
+                }
+                // targeted behaviours do nothing without a target
+                _ => Vec3::ZERO,
+            };
+            forces.push((force, weight));
+        }
+
+        let steering_force = combine(forces, steering.max_acceleration);
+        // never accelerate past max_speed
+        let delta_seconds = time.delta_seconds();
+        acceleration.value = if delta_seconds > 0.0 {
+            let next_velocity =
+                (velocity + steering_force * delta_seconds).clamp_length_max(max_speed);
+            (next_velocity - velocity) / delta_seconds
+        } else {
+            steering_force
+        };
+    }
+}
+
+fn face_velocity(mut query: Query<(&Velocity, &mut Transform), With<FaceVelocity>>) {
+    for (velocity, mut transform) in query.iter_mut() {
+        if let Some(direction) = velocity.value.try_normalize() {
+            transform.look_to(-direction, Vec3::Y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    fn assert_close(actual: Vec3, expected: Vec3) {
+        assert!(
+            actual.abs_diff_eq(expected, EPSILON),
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn seek_accelerates_towards_target_at_max_speed() {
+        let force = seek(Vec3::ZERO, Vec3::ZERO, Vec3::new(10.0, 0.0, 0.0), 5.0);
+        assert_close(force, Vec3::new(5.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn seek_corrects_current_velocity() {
+        let force = seek(
+            Vec3::ZERO,
+            Vec3::new(0.0, 0.0, 5.0),
+            Vec3::new(10.0, 0.0, 0.0),
+            5.0,
+        );
+        assert_close(force, Vec3::new(5.0, 0.0, -5.0));
+    }
+
+    #[test]
+    fn flee_accelerates_away_from_target() {
+        let force = flee(Vec3::ZERO, Vec3::ZERO, Vec3::new(10.0, 0.0, 0.0), 5.0);
+        assert_close(force, Vec3::new(-5.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn arrive_goes_full_speed_outside_slowing_radius() {
+        let force = arrive(
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::new(0.0, 0.0, 20.0),
+            4.0,
+            5.0,
+            0.0,
+        );
+        assert_close(force, Vec3::new(0.0, 0.0, 4.0));
+    }
+
+    #[test]
+    fn arrive_slows_inside_slowing_radius() {
+        let force = arrive(
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::new(0.0, 0.0, 2.5),
+            4.0,
+            5.0,
+            0.0,
+        );
+        assert_close(force, Vec3::new(0.0, 0.0, 2.0));
+    }
+
+    #[test]
+    fn arrive_backs_off_inside_standoff() {
+        let force = arrive(
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::new(0.0, 0.0, 10.0),
+            4.0,
+            5.0,
+            12.5,
+        );
+        assert_close(force, Vec3::new(0.0, 0.0, -2.0));
+    }
+
+    #[test]
+    fn arrive_brakes_on_target() {
+        let force = arrive(
+            Vec3::ONE,
+            Vec3::new(1.0, 0.0, 2.0),
+            Vec3::ONE,
+            4.0,
+            5.0,
+            0.0,
+        );
+        assert_close(force, Vec3::new(-1.0, 0.0, -2.0));
+    }
+
+    #[test]
+    fn pursue_leads_a_moving_target() {
+        // 10 away at speed 10 is one second, by which time the target has moved 10 along z
+        let force = pursue(
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::new(10.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 10.0),
+            10.0,
+        );
+        let expected = Vec3::new(1.0, 0.0, 1.0).normalize() * 10.0;
+        assert_close(force, expected);
+    }
+
+    #[test]
+    fn evade_flees_from_predicted_position() {
+        let force = evade(
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::new(10.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 10.0),
+            10.0,
+        );
+        let expected = Vec3::new(-1.0, 0.0, -1.0).normalize() * 10.0;
+        assert_close(force, expected);
+    }
+
+    #[test]
+    fn wander_seeks_a_point_on_the_circle_ahead() {
+        // heading +z, circle centred 4 ahead, angle 0 puts the point 3 to the +x side
+        let force = wander(Vec3::ZERO, Vec3::new(0.0, 0.0, 1.0), 5.0, 4.0, 3.0, 0.0);
+        let expected = Vec3::new(3.0, 0.0, 4.0).normalize() * 5.0 - Vec3::new(0.0, 0.0, 1.0);
+        assert_close(force, expected);
+    }
+
+    #[test]
+    fn avoid_obstacles_ignores_clear_path() {
+        let obstacles = [
+            (Vec3::new(10.0, 0.0, 5.0), 1.0),
+            (Vec3::new(0.0, 0.0, -5.0), 1.0),
+            (Vec3::new(0.0, 0.0, 50.0), 1.0),
+        ];
+        let force = avoid_obstacles(
+            Vec3::ZERO,
+            Vec3::new(0.0, 0.0, 1.0),
+            1.0,
+            10.0,
+            20.0,
+            obstacles,
+        );
+        assert_close(force, Vec3::ZERO);
+    }
+
+    #[test]
+    fn avoid_obstacles_pushes_away_from_nearest_obstacle_ahead() {
+        let obstacles = [
+            (Vec3::new(0.5, 0.0, 15.0), 2.0),
+            (Vec3::new(1.0, 0.0, 5.0), 2.0),
+        ];
+        let force = avoid_obstacles(
+            Vec3::ZERO,
+            Vec3::new(0.0, 0.0, 1.0),
+            1.0,
+            10.0,
+            20.0,
+            obstacles,
+        );
+        // the nearer obstacle is a quarter of the way along and off to +x
+        assert_close(force, Vec3::new(-7.5, 0.0, 0.0));
+    }
+
+    #[test]
+    fn avoid_obstacles_does_nothing_when_stationary() {
+        let force = avoid_obstacles(
+            Vec3::ZERO,
+            Vec3::ZERO,
+            1.0,
+            10.0,
+            20.0,
+            [(Vec3::new(0.0, 0.0, 2.0), 2.0)],
+        );
+        assert_close(force, Vec3::ZERO);
+    }
+
+    #[test]
+    fn separate_pushes_away_from_close_neighbours() {
+        let neighbours = [
+            (Vec3::new(1.0, 0.0, 0.0), Vec3::ZERO),
+            (Vec3::new(0.0, 0.0, 10.0), Vec3::ZERO),
+        ];
+        let force = separate(Vec3::ZERO, Vec3::ZERO, 5.0, 4.0, &neighbours);
+        assert_close(force, Vec3::new(-5.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn separate_ignores_distant_neighbours() {
+        let neighbours = [(Vec3::new(0.0, 0.0, 10.0), Vec3::ZERO)];
+        let force = separate(Vec3::ZERO, Vec3::new(1.0, 0.0, 0.0), 5.0, 4.0, &neighbours);
+        assert_close(force, Vec3::ZERO);
+    }
+
+    #[test]
+    fn align_matches_average_heading() {
+        let neighbours = [
+            (Vec3::new(1.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0)),
+            (Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 2.0)),
+            (Vec3::new(20.0, 0.0, 0.0), Vec3::new(-9.0, 0.0, 0.0)),
+        ];
+        let force = align(Vec3::ZERO, Vec3::ZERO, 5.0, 4.0, &neighbours);
+        assert_close(force, Vec3::new(1.0, 0.0, 1.0).normalize() * 5.0);
+    }
+
+    #[test]
+    fn cohere_seeks_center_of_neighbours() {
+        let neighbours = [
+            (Vec3::new(2.0, 0.0, 2.0), Vec3::ZERO),
+            (Vec3::new(2.0, 0.0, -2.0), Vec3::ZERO),
+            (Vec3::new(-20.0, 0.0, 0.0), Vec3::ZERO),
+        ];
+        let force = cohere(Vec3::ZERO, Vec3::ZERO, 5.0, 4.0, &neighbours);
+        assert_close(force, Vec3::new(5.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn flocking_does_nothing_alone() {
+        let velocity = Vec3::new(1.0, 0.0, 0.0);
+        assert_close(separate(Vec3::ZERO, velocity, 5.0, 4.0, &[]), Vec3::ZERO);
+        assert_close(align(Vec3::ZERO, velocity, 5.0, 4.0, &[]), Vec3::ZERO);
+        assert_close(cohere(Vec3::ZERO, velocity, 5.0, 4.0, &[]), Vec3::ZERO);
+    }
+
+    #[test]
+    fn combine_weights_and_clamps() {
+        let forces = [
+            (Vec3::new(1.0, 0.0, 0.0), 2.0),
+            (Vec3::new(0.0, 0.0, 1.0), 0.5),
+        ];
+        assert_close(combine(forces, 10.0), Vec3::new(2.0, 0.0, 0.5));
+
+        let clamped = combine(forces, 1.0);
+        assert!((clamped.length() - 1.0).abs() < EPSILON);
+        assert_close(clamped, Vec3::new(2.0, 0.0, 0.5).normalize());
+    }
+}
@@ -0,0 +1,232 @@
+use bevy::prelude::*;
+use std::f32::consts::PI;
+
+use crate::boss::Boss;
+use crate::collision::{Collider, CollisionDamage, StaticCollider};
+use crate::despawn::DespawnOnRunEnd;
+use crate::enemy::Enemy;
+use crate::faction::{Faction, Hostility};
+use crate::health::Health;
+use crate::schedule::InGameSet;
+use crate::spaceship::{angle_to, turn_towards, Spaceship};
+use crate::spatial::SpatialGrid;
+use crate::state::OnRunStart;
+use crate::weapon::{FireWeaponEvent, Weapon};
+
+const STATION_TRANSLATIONS: [Vec3; 1] = [Vec3::new(40.0, 0.0, 10.0)];
+const STATION_RADIUS: f32 = 6.0;
+const STATION_HEALTH: f32 = 500.0;
+const STATION_COLOR: Color = Color::rgb(0.5, 0.5, 0.6);
+const TURRET_TRANSLATIONS: [Vec3; 2] = [Vec3::new(28.0, 0.0, 2.0), Vec3::new(28.0, 0.0, 18.0)];
+const TURRET_RADIUS: f32 = 2.5;
+const TURRET_HEALTH: f32 = 120.0;
+const TURRET_RANGE: f32 = 45.0;
+const TURRET_ROTATION_SPEED: f32 = 1.5;
+const TURRET_FIRE_ARC: f32 = 0.1;
+const TURRET_SECONDS_BETWEEN_SHOTS: f32 = 0.8;
+const TURRET_BARREL_LENGTH: f32 = 4.0;
+const TURRET_COLOR: Color = Color::rgb(0.6, 0.2, 0.2);
+const WALLS: [(Vec3, Vec3); 1] = [(Vec3::new(-45.0, 0.0, -5.0), Vec3::new(-45.0, 0.0, 20.0))];
+const WALL_BLOCK_RADIUS: f32 = 1.5;
+const WALL_BLOCK_HEALTH: f32 = 60.0;
+const WALL_COLOR: Color = Color::rgb(0.35, 0.3, 0.25);
+const STRUCTURE_COLLISION_DAMAGE: f32 = 35.0;
+
+/// Anything built into the arena rather than flying around it. Structures have a `Collider` and
+/// `Health` but no movement components.
+#[derive(Component, Debug)]
+pub struct Structure;
+
+/// Turns towards the nearest hostile ship within `range` and fires once it's lined up.
+/// Sits on a turret's barrel, which carries the `Weapon`.
+#[derive(Component, Debug)]
+pub struct TurretGun {
+    pub range: f32,
+    pub rotation_speed: f32,
+    /// Fires with the target within this angle either side of the barrel, in radians.
+    pub fire_arc: f32,
+}
+
+impl TurretGun {
+    pub fn new(range: f32, rotation_speed: f32, fire_arc: f32) -> Self {
+        Self {
+            range,
+            rotation_speed,
+            fire_arc,
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+struct StructureAssets {
+    station_mesh: Handle<Mesh>,
+    station_material: Handle<StandardMaterial>,
+    turret_mesh: Handle<Mesh>,
+    barrel_mesh: Handle<Mesh>,
+    turret_material: Handle<StandardMaterial>,
+    wall_mesh: Handle<Mesh>,
+    wall_material: Handle<StandardMaterial>,
+}
+
+pub struct StructurePlugin;
+
+impl Plugin for StructurePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, create_structure_assets)
+            .add_systems(OnRunStart, spawn_structures)
+            .add_systems(Update, turret_controls.in_set(InGameSet::UserInput));
+    }
+}
+
+fn create_structure_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let material = |color: Color| StandardMaterial {
+        base_color: color,
+        ..default()
+    };
+    commands.insert_resource(StructureAssets {
+        station_mesh: meshes.add(Torus::new(STATION_RADIUS * 0.5, STATION_RADIUS)),
+        station_material: materials.add(material(STATION_COLOR)),
+        turret_mesh: meshes.add(Cylinder::new(TURRET_RADIUS, 1.5)),
+        barrel_mesh: meshes.add(Cuboid::new(0.6, 0.6, TURRET_BARREL_LENGTH)),
+        turret_material: materials.add(material(TURRET_COLOR)),
+        wall_mesh: meshes.add(Cuboid::from_size(Vec3::splat(WALL_BLOCK_RADIUS * 2.0))),
+        wall_material: materials.add(material(WALL_COLOR)),
+    });
+}
+
+fn spawn_structures(mut commands: Commands, assets: Res<StructureAssets>) {
+    for translation in STATION_TRANSLATIONS {
+        commands.spawn((
+            PbrBundle {
+                mesh: assets.station_mesh.clone(),
+                material: assets.station_material.clone(),
+                transform: Transform::from_translation(translation),
+                ..default()
+            },
+            Structure,
+            StaticCollider,
+            Collider::new(STATION_RADIUS),
+            Health::new(STATION_HEALTH),
+            CollisionDamage::new(STRUCTURE_COLLISION_DAMAGE),
+            Faction::Enemy,
+            DespawnOnRunEnd,
+        ));
+    }
+
+    for translation in TURRET_TRANSLATIONS {
+        commands
+            .spawn((
+                PbrBundle {
+                    mesh: assets.turret_mesh.clone(),
+                    material: assets.turret_material.clone(),
+                    transform: Transform::from_translation(translation),
+                    ..default()
+                },
+                Structure,
+                StaticCollider,
+                Collider::new(TURRET_RADIUS),
+                Health::new(TURRET_HEALTH),
+                CollisionDamage::new(STRUCTURE_COLLISION_DAMAGE),
+                Faction::Enemy,
+                DespawnOnRunEnd,
+            ))
+            .with_children(|parent| {
+                // the barrel pivots on the turret, pointing down the arena to start with
+                parent
+                    .spawn((
+                        SpatialBundle::from_transform(
+                            Transform::from_xyz(0.0, 1.0, 0.0)
+                                .with_rotation(Quat::from_rotation_y(PI)),
+                        ),
+                        TurretGun::new(TURRET_RANGE, TURRET_ROTATION_SPEED, TURRET_FIRE_ARC),
+                        Weapon::new(TURRET_SECONDS_BETWEEN_SHOTS),
+                        Faction::Enemy,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(PbrBundle {
+                            mesh: assets.barrel_mesh.clone(),
+                            material: assets.turret_material.clone(),
+                            transform: Transform::from_xyz(0.0, 0.0, TURRET_BARREL_LENGTH / 2.0),
+                            ..default()
+                        });
+                    });
+            });
+    }
+
+    for (start, end) in WALLS {
+        let blocks = (start.distance(end) / (WALL_BLOCK_RADIUS * 2.0))
+            .ceil()
+            .max(1.0) as usize;
+        for block in 0..=blocks {
+            let translation = start.lerp(end, block as f32 / blocks as f32);
+            commands.spawn((
+                PbrBundle {
+                    mesh: assets.wall_mesh.clone(),
+                    material: assets.wall_material.clone(),
+                    transform: Transform::from_translation(translation),
+                    ..default()
+                },
+                Structure,
+                StaticCollider,
+                Collider::new(WALL_BLOCK_RADIUS),
+                Health::new(WALL_BLOCK_HEALTH),
+                CollisionDamage::new(STRUCTURE_COLLISION_DAMAGE),
+                Faction::Neutral,
+                DespawnOnRunEnd,
+            ));
+        }
+    }
+}
+
+/// Turns each turret's barrel towards the nearest ship its faction is hostile to and fires
+/// when it's lined up.
+#[allow(clippy::type_complexity)]
+fn turret_controls(
+    mut fire_weapon_event_writer: EventWriter<FireWeaponEvent>,
+    mut gun_query: Query<(
+        Entity,
+        &TurretGun,
+        &GlobalTransform,
+        &mut Transform,
+        &Faction,
+    )>,
+    target_query: Query<Option<&Faction>, Or<(With<Spaceship>, With<Enemy>, With<Boss>)>>,
+    grid: Res<SpatialGrid>,
+    hostility: Res<Hostility>,
+    time: Res<Time>,
+) {
+    for (entity, gun, global_transform, mut transform, &faction) in gun_query.iter_mut() {
+        let position = global_transform.translation();
+        let Some(target) = grid
+            .query(position, gun.range)
+            .into_iter()
+            .filter(|entry| {
+                target_query.get(entry.entity).is_ok_and(|target_faction| {
+                    hostility.is_hostile(Some(faction), target_faction.copied())
+                })
+            })
+            .min_by(|a, b| {
+                let a = a.position.distance_squared(position);
+                let b = b.position.distance_squared(position);
+                a.total_cmp(&b)
+            })
+        else {
+            continue;
+        };
+
+        let Some(angle) = angle_to(&global_transform.compute_transform(), target.position) else {
+            continue;
+        };
+        let turn = turn_towards(angle, gun.rotation_speed, time.delta_seconds());
+        // the turret itself never turns, so turning the barrel locally turns it in the world
+        transform.rotate_y(-turn * gun.rotation_speed * time.delta_seconds());
+
+        if angle.abs() <= gun.fire_arc {
+            fire_weapon_event_writer.send(FireWeaponEvent::new(entity));
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+use std::f32::consts::TAU;
+use std::ops::Range;
+
+use crate::asset_loader::SceneAssets;
+use crate::boss::{Boss, BossKind, BossSpawner};
+use crate::config::ConfigPlugin;
+use crate::enemy::{spawn_enemy, Enemy, EnemyKind, EnemyProfiles};
+use crate::rng::GameRng;
+use crate::schedule::InGameSet;
+use crate::state::OnRunStart;
+
+const WAVES_PATH: &str = "config/waves.ron";
+const SPAWN_RANGE_X: Range<f32> = -40.0..40.0;
+const SPAWN_Z: f32 = 45.0;
+/// How far apart enemies that arrive in a group can spawn.
+const GROUP_SPREAD: f32 = 8.0;
+const BREAK_SECONDS: f32 = 4.0;
+/// Once past the last defined wave, it repeats with this many more enemies each time.
+const EXTRA_ENEMIES_PER_REPEAT: f32 = 0.5;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WaveDefinition {
+    pub enemies: Vec<(EnemyKind, u32)>,
+    pub seconds_between_spawns: f32,
+    /// Arrives at the start of the wave, ahead of the other enemies.
+    #[serde(default)]
+    pub boss: Option<BossKind>,
+}
+
+/// Every wave in order, loaded from `WAVES_PATH`. With none loaded, waves come and go empty.
+#[derive(Asset, Resource, TypePath, Debug, Clone, Default, Deserialize)]
+pub struct WaveDefinitions {
+    waves: Vec<WaveDefinition>,
+}
+
+impl WaveDefinitions {
+    /// The definition used for the given wave, counting from 0, and how many times it has
+    /// been repeated.
+    fn definition(&self, wave: u32) -> Option<(&WaveDefinition, usize)> {
+        let last = self.waves.len().checked_sub(1)?;
+        let repeats = (wave as usize).saturating_sub(last);
+        Some((&self.waves[(wave as usize).min(last)], repeats))
+    }
+
+    /// The enemies to spawn in the given wave, counting from 0, in spawn order.
+    fn enemies(&self, wave: u32) -> (Vec<EnemyKind>, f32) {
+        let Some((definition, repeats)) = self.definition(wave) else {
+            return default();
+        };
+        let scale = 1.0 + repeats as f32 * EXTRA_ENEMIES_PER_REPEAT;
+
+        let mut enemies = vec![];
+        for &(kind, count) in definition.enemies.iter() {
+            let count = (count as f32 * scale).round() as usize;
+            enemies.extend(std::iter::repeat_n(kind, count));
+        }
+        (enemies, definition.seconds_between_spawns)
+    }
+
+    fn boss(&self, wave: u32) -> Option<BossKind> {
+        self.definition(wave)
+            .and_then(|(definition, _)| definition.boss)
+    }
+}
+
+/// Progress through the waves of the current run.
+#[derive(Resource, Debug)]
+pub struct WaveState {
+    /// The wave in progress, or about to start, counting from 0.
+    pub wave: u32,
+    pub started: bool,
+    pending: Vec<EnemyKind>,
+    pending_boss: Option<BossKind>,
+    spawn_timer: Timer,
+    break_timer: Timer,
+}
+
+impl Default for WaveState {
+    fn default() -> Self {
+        Self {
+            wave: 0,
+            started: false,
+            pending: vec![],
+            pending_boss: None,
+            spawn_timer: Timer::from_seconds(0.0, TimerMode::Repeating),
+            break_timer: Timer::from_seconds(BREAK_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+#[derive(Event, Debug)]
+pub struct WaveStarted {
+    /// Counting from 1, as shown to the player.
+    pub number: u32,
+}
+
+impl WaveStarted {
+    pub fn new(number: u32) -> Self {
+        Self { number }
+    }
+}
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ConfigPlugin::<WaveDefinitions>::new(WAVES_PATH))
+            .init_resource::<WaveState>()
+            .add_systems(OnRunStart, reset_waves)
+            .add_systems(
+                Update,
+                (advance_waves, spawn_wave_enemies)
+                    .chain()
+                    .in_set(InGameSet::EntityUpdates),
+            )
+            .add_event::<WaveStarted>();
+    }
+}
+
+fn reset_waves(mut wave_state: ResMut<WaveState>) {
+    *wave_state = WaveState::default();
+}
+
+/// Starts the next wave after a short break once every enemy and boss of the last one is gone.
+#[allow(clippy::type_complexity)]
+fn advance_waves(
+    mut wave_started_event_writer: EventWriter<WaveStarted>,
+    mut wave_state: ResMut<WaveState>,
+    enemy_query: Query<(), Or<(With<Enemy>, With<Boss>)>>,
+    definitions: Res<WaveDefinitions>,
+    time: Res<Time>,
+) {
+    if !wave_state.pending.is_empty() || wave_state.pending_boss.is_some() {
+        return;
+    }
+    if !enemy_query.is_empty() {
+        return;
+    }
+    if !wave_state.break_timer.tick(time.delta()).finished() {
+        return;
+    }
+
+    if wave_state.started {
+        wave_state.wave += 1;
+    }
+    let (enemies, seconds_between_spawns) = definitions.enemies(wave_state.wave);
+    wave_state.started = true;
+    wave_state.pending = enemies;
+    wave_state.pending_boss = definitions.boss(wave_state.wave);
+    wave_state.spawn_timer = Timer::from_seconds(seconds_between_spawns, TimerMode::Repeating);
+    wave_state.break_timer.reset();
+    wave_started_event_writer.send(WaveStarted::new(wave_state.wave + 1));
+}
+
+fn spawn_wave_enemies(
+    mut commands: Commands,
+    mut wave_state: ResMut<WaveState>,
+    scene_assets: Res<SceneAssets>,
+    profiles: Res<EnemyProfiles>,
+    boss_spawner: BossSpawner,
+    mut rng: ResMut<GameRng>,
+    time: Res<Time>,
+) {
+    if let Some(kind) = wave_state.pending_boss.take() {
+        boss_spawner.spawn(&mut commands, kind, Vec3::new(0.0, 0.0, SPAWN_Z));
+    }
+    if wave_state.pending.is_empty() || !wave_state.spawn_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let kind = wave_state.pending.remove(0);
+    let group_size = profiles
+        .get(kind)
+        .map_or(1, |profile| profile.group_size.max(1));
+    let center = Vec3::new(rng.gen_range(SPAWN_RANGE_X), 0.0, SPAWN_Z);
+    for _ in 0..group_size {
+        let offset = if group_size > 1 {
+            let angle = rng.gen_range(0.0..TAU);
+            let distance = rng.gen_range(0.0..GROUP_SPREAD);
+            Vec3::new(angle.cos(), 0.0, angle.sin()) * distance
+        } else {
+            Vec3::ZERO
+        };
+        spawn_enemy(
+            &mut commands,
+            &scene_assets,
+            &profiles,
+            kind,
+            center + offset,
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+use crate::asset_loader::SceneAssets;
+use crate::boss::Boss;
+use crate::collision::{Collider, CollisionDamage};
+use crate::despawn::{DespawnOnRunEnd, DespawnWhenRemote};
+use crate::enemy::Enemy;
+use crate::faction::{Faction, Hostility};
+use crate::health::Health;
+use crate::movement::{Acceleration, MovingObjectBundle, Velocity};
+use crate::player::Player;
+use crate::powerup::{RapidFire, TripleShot};
+use crate::schedule::InGameSet;
+use crate::spaceship::Spaceship;
+use crate::steering::{FaceVelocity, Steering, SteeringBehaviour};
+
+const PROJECTILE_RADIUS: f32 = 1.;
+const PROJECTILE_SPEED: f32 = 55.0;
+const PROJECTILE_FORWARD_SPAWN: f32 = 6.5;
+const PROJECTILE_HEALTH: f32 = 1.0;
+const PROJECTILE_COLLISION_DAMAGE: f32 = 5.0;
+const TRIPLE_SHOT_SPREAD: f32 = 0.2;
+
+#[derive(Component, Debug)]
+pub struct Projectile {
+    pub shooter: Entity,
+    /// The player who fired it, kept here so the shot still scores after their ship is gone.
+    pub player: Option<Player>,
+}
+
+impl Projectile {
+    pub fn new(shooter: Entity, player: Option<Player>) -> Self {
+        Self { shooter, player }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Weapon {
+    pub cooldown: Timer,
+    pub ammo: Option<u32>,
+    pub heat_per_shot: f32,
+    pub energy_per_shot: f32,
+    /// Makes projectiles home in on the nearest opponent, steering this hard.
+    pub homing_acceleration: Option<f32>,
+}
+
+impl Weapon {
+    pub fn new(seconds_between_shots: f32) -> Self {
+        let mut cooldown = Timer::from_seconds(seconds_between_shots, TimerMode::Once);
+        // start ready to fire
+        cooldown.tick(cooldown.duration());
+        Self {
+            cooldown,
+            ammo: None,
+            heat_per_shot: 0.0,
+            energy_per_shot: 0.0,
+            homing_acceleration: None,
+        }
+    }
+
+    pub fn with_heat_per_shot(mut self, heat_per_shot: f32) -> Self {
+        self.heat_per_shot = heat_per_shot;
+        self
+    }
+
+    pub fn with_energy_per_shot(mut self, energy_per_shot: f32) -> Self {
+        self.energy_per_shot = energy_per_shot;
+        self
+    }
+
+    pub fn has_ammo(&self) -> bool {
+        self.ammo.is_none_or(|ammo| ammo > 0)
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Heat {
+    pub value: f32,
+    pub max: f32,
+    pub cooling_rate: f32,
+    pub overheated: bool,
+    pub lockout: Timer,
+}
+
+impl Heat {
+    pub fn new(max: f32, cooling_rate: f32, lockout_seconds: f32) -> Self {
+        Self {
+            value: 0.0,
+            max,
+            cooling_rate,
+            overheated: false,
+            lockout: Timer::from_seconds(lockout_seconds, TimerMode::Once),
+        }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        (self.value / self.max).clamp(0.0, 1.0)
+    }
+
+    fn add(&mut self, amount: f32) {
+        self.value = (self.value + amount).min(self.max);
+        if self.value >= self.max {
+            self.overheated = true;
+            self.lockout.reset();
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Energy {
+    pub value: f32,
+    pub max: f32,
+    pub regen_rate: f32,
+}
+
+impl Energy {
+    pub fn new(max: f32, regen_rate: f32) -> Self {
+        Self {
+            value: max,
+            max,
+            regen_rate,
+        }
+    }
+
+    /// Spends `amount` energy if there is enough of it, returning whether it was spent.
+    pub fn try_consume(&mut self, amount: f32) -> bool {
+        if self.value < amount {
+            return false;
+        }
+        self.value -= amount;
+        true
+    }
+}
+
+#[derive(Event, Debug)]
+pub struct FireWeaponEvent {
+    pub entity: Entity,
+}
+
+impl FireWeaponEvent {
+    pub fn new(entity: Entity) -> Self {
+        Self { entity }
+    }
+}
+
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (tick_weapon_cooldowns, cool_weapons, regenerate_energy),
+                fire_weapons,
+            )
+                .chain()
+                .in_set(InGameSet::EntityUpdates),
+        )
+        .add_event::<FireWeaponEvent>();
+    }
+}
+
+fn tick_weapon_cooldowns(mut query: Query<(&mut Weapon, Option<&RapidFire>)>, time: Res<Time>) {
+    for (mut weapon, rapid_fire) in query.iter_mut() {
+        let multiplier = rapid_fire.map_or(1.0, |rapid_fire| rapid_fire.multiplier);
+        weapon.cooldown.tick(time.delta().mul_f32(multiplier));
+    }
+}
+
+fn cool_weapons(mut query: Query<&mut Heat>, time: Res<Time>) {
+    for mut heat in query.iter_mut() {
+        heat.value = (heat.value - heat.cooling_rate * time.delta_seconds()).max(0.0);
+        if heat.overheated && heat.lockout.tick(time.delta()).finished() {
+            heat.overheated = false;
+        }
+    }
+}
+
+fn regenerate_energy(mut query: Query<&mut Energy>, time: Res<Time>) {
+    for mut energy in query.iter_mut() {
+        energy.value = (energy.value + energy.regen_rate * time.delta_seconds()).min(energy.max);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn fire_weapons(
+    mut commands: Commands,
+    mut fire_weapon_event_reader: EventReader<FireWeaponEvent>,
+    mut query: Query<(
+        &GlobalTransform,
+        &mut Weapon,
+        Option<&mut Heat>,
+        Option<&mut Energy>,
+        (Has<TripleShot>, Option<&Faction>, Option<&Player>),
+    )>,
+    target_query: Query<
+        (Entity, &GlobalTransform, Option<&Faction>),
+        Or<(With<Spaceship>, With<Enemy>, With<Boss>)>,
+    >,
+    scene_assets: Res<SceneAssets>,
+    hostility: Res<Hostility>,
+) {
+    for &FireWeaponEvent { entity } in fire_weapon_event_reader.read() {
+        let Ok((
+            global_transform,
+            mut weapon,
+            mut heat,
+            mut energy,
+            (triple_shot, faction, player),
+        )) = query.get_mut(entity)
+        else {
+            continue;
+        };
+        let faction = faction.copied().unwrap_or_default();
+
+        if !weapon.cooldown.finished() || !weapon.has_ammo() {
+            continue;
+        }
+        if heat.as_ref().is_some_and(|heat| heat.overheated) {
+            continue;
+        }
+        if let Some(energy) = energy.as_mut() {
+            if !energy.try_consume(weapon.energy_per_shot) {
+                continue;
+            }
+        }
+
+        weapon.cooldown.reset();
+        if let Some(ammo) = weapon.ammo.as_mut() {
+            *ammo -= 1;
+        }
+        if let Some(heat) = heat.as_mut() {
+            heat.add(weapon.heat_per_shot);
+        }
+
+        let transform = global_transform.compute_transform();
+        let homing = weapon.homing_acceleration.and_then(|acceleration| {
+            let target = nearest_opponent(
+                entity,
+                faction,
+                transform.translation,
+                &target_query,
+                &hostility,
+            )?;
+            Some(
+                Steering::new(PROJECTILE_SPEED, acceleration)
+                    .with_behaviour(SteeringBehaviour::Pursue, 1.0)
+                    .with_target(target),
+            )
+        });
+        let spread: &[f32] = if triple_shot {
+            &[-TRIPLE_SHOT_SPREAD, 0.0, TRIPLE_SHOT_SPREAD]
+        } else {
+            &[0.0]
+        };
+        for &angle in spread {
+            let rotation = Quat::from_rotation_y(angle) * transform.rotation;
+            let translation = transform.translation + rotation * Vec3::Z * PROJECTILE_FORWARD_SPAWN;
+            let mut projectile = spawn_projectile(
+                &mut commands,
+                &scene_assets,
+                entity,
+                player.copied(),
+                faction,
+                translation,
+                rotation,
+            );
+            if let Some(steering) = homing.clone() {
+                projectile.insert((steering, FaceVelocity));
+            }
+        }
+    }
+}
+
+/// Spawns a projectile fired by `shooter`, on its side, flying along the local +Z of `rotation`.
+/// Anything it destroys is credited to `player`, if a player fired it.
+pub fn spawn_projectile<'a>(
+    commands: &'a mut Commands,
+    scene_assets: &SceneAssets,
+    shooter: Entity,
+    player: Option<Player>,
+    faction: Faction,
+    translation: Vec3,
+    rotation: Quat,
+) -> EntityCommands<'a> {
+    let direction = rotation * Vec3::Z;
+    commands.spawn((
+        MovingObjectBundle {
+            acceleration: Acceleration::new(Vec3::ZERO),
+            collider: Collider::new(PROJECTILE_RADIUS),
+            velocity: Velocity::new(direction * PROJECTILE_SPEED),
+            model: SceneBundle {
+                scene: scene_assets.missile.clone(),
+                transform: Transform::from_translation(translation)
+                    .with_rotation(rotation)
+                    .with_scale(Vec3::splat(0.25)),
+                ..default()
+            },
+        },
+        Projectile::new(shooter, player),
+        faction,
+        DespawnWhenRemote,
+        DespawnOnRunEnd,
+        Health::new(PROJECTILE_HEALTH),
+        CollisionDamage::new(PROJECTILE_COLLISION_DAMAGE),
+    ))
+}
+
+/// The closest ship to `shooter` that its faction is hostile to.
+#[allow(clippy::type_complexity)]
+fn nearest_opponent(
+    shooter: Entity,
+    faction: Faction,
+    translation: Vec3,
+    target_query: &Query<
+        (Entity, &GlobalTransform, Option<&Faction>),
+        Or<(With<Spaceship>, With<Enemy>, With<Boss>)>,
+    >,
+    hostility: &Hostility,
+) -> Option<Entity> {
+    target_query
+        .iter()
+        .filter(|&(target, _, target_faction)| {
+            target != shooter && hostility.is_hostile(Some(faction), target_faction.copied())
+        })
+        .min_by(|(_, a, _), (_, b, _)| {
+            let a = a.translation().distance_squared(translation);
+            let b = b.translation().distance_squared(translation);
+            a.total_cmp(&b)
+        })
+        .map(|(entity, _, _)| entity)
+}